@@ -0,0 +1,165 @@
+use anyhow::{bail, Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use tar::{Archive, Builder, EntryType, Header};
+
+use crate::{manifest::Manifest, staging::StagingDir};
+
+/// Compression of a metadata archive, detected by file extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Ok(ArchiveFormat::TarZst)
+        } else {
+            bail!(
+                "Cannot detect archive format of {}, expected .tar.gz, .tgz, .tar.zst or .tzst",
+                path.display()
+            )
+        }
+    }
+}
+
+/// Packs the files below `folder` into `archive`. Entries are sorted by path and carry no
+/// timestamps, owners or permission bits other than 0644 and 0755, so the same folder always
+/// produces the same archive bytes.
+pub fn create_archive(folder: &Path, archive: &Path) -> Result<()> {
+    let format = ArchiveFormat::from_path(archive)?;
+    let mut tar = Builder::new(Vec::new());
+    append_dir(&mut tar, folder, Path::new(""))?;
+    let tar = tar.into_inner()?;
+
+    let compressed = match format {
+        ArchiveFormat::TarGz => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&tar)?;
+            encoder.finish()?
+        }
+        ArchiveFormat::TarZst => zstd::encode_all(tar.as_slice(), 0)?,
+    };
+    fs::write(archive, compressed).with_context(|| format!("Cannot write {}", archive.display()))
+}
+
+fn append_dir<W: Write>(tar: &mut Builder<W>, folder: &Path, prefix: &Path) -> Result<()> {
+    let mut entries = fs::read_dir(folder)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    entries.sort();
+    for path in entries {
+        let name = prefix.join(path.file_name().unwrap_or_default());
+        let mut header = Header::new_ustar();
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+        if path.is_dir() {
+            header.set_entry_type(EntryType::Directory);
+            header.set_mode(0o755);
+            header.set_size(0);
+            tar.append_data(&mut header, &name, std::io::empty())?;
+            append_dir(tar, &path, &name)?;
+        } else {
+            let contents =
+                fs::read(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+            header.set_entry_type(EntryType::Regular);
+            header.set_mode(0o644);
+            header.set_size(contents.len() as u64);
+            tar.append_data(&mut header, &name, contents.as_slice())?;
+        }
+    }
+    Ok(())
+}
+
+/// Unpacks `archive` into the metadata folder `target`, which must not exist yet. The files are
+/// checked against the archived `manifest.json` before anything is moved into place.
+pub fn extract_archive(archive: &Path, target: &Path) -> Result<()> {
+    if target.exists() {
+        bail!("{} already exists", target.display());
+    }
+    let reader: Box<dyn Read> = {
+        let file =
+            File::open(archive).with_context(|| format!("Cannot open {}", archive.display()))?;
+        match ArchiveFormat::from_path(archive)? {
+            ArchiveFormat::TarGz => Box::new(GzDecoder::new(file)),
+            ArchiveFormat::TarZst => Box::new(zstd::Decoder::new(file)?),
+        }
+    };
+
+    let staging = StagingDir::begin(target)?;
+    Archive::new(reader)
+        .unpack(staging.path())
+        .with_context(|| format!("Cannot unpack {}", archive.display()))?;
+    Manifest::read(staging.path())?.verify_files(staging.path())?;
+    staging.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::genesis_config::GenesisConfig;
+
+    fn metadata_folder(dir: &Path) -> PathBuf {
+        let folder = dir.join("metadata");
+        fs::create_dir_all(folder.join("keystores")).unwrap();
+        fs::write(folder.join("accounts.json"), "[]").unwrap();
+        fs::write(folder.join("keystores/a.json"), "{}").unwrap();
+        Manifest::new(&GenesisConfig::default(), &folder)
+            .unwrap()
+            .write(&folder)
+            .unwrap();
+        folder
+    }
+
+    #[test]
+    fn test_archives_are_reproducible_and_extract_verified() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = metadata_folder(dir.path());
+
+        for name in ["network.tar.gz", "network.tar.zst"] {
+            let archive = dir.path().join(name);
+            create_archive(&folder, &archive).unwrap();
+            let bytes = fs::read(&archive).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            fs::write(folder.join("accounts.json"), "[]").unwrap();
+            create_archive(&folder, &archive).unwrap();
+            assert!(fs::read(&archive).unwrap() == bytes, "{} differs", name);
+
+            let extracted = dir.path().join(format!("extracted-{}", name));
+            extract_archive(&archive, &extracted).unwrap();
+            assert_eq!(
+                fs::read_to_string(extracted.join("keystores/a.json")).unwrap(),
+                "{}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_rejects_files_not_matching_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = metadata_folder(dir.path());
+        fs::write(folder.join("accounts.json"), "{}").unwrap();
+        let archive = dir.path().join("network.tgz");
+        create_archive(&folder, &archive).unwrap();
+
+        let target = dir.path().join("extracted");
+        let error = extract_archive(&archive, &target).unwrap_err();
+        assert!(format!("{:#}", error).contains("accounts.json has sha256"));
+        assert!(!target.exists());
+    }
+}
@@ -1,8 +1,27 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
+use crate::resolve::ResolvedFormat;
+
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
 pub struct EthereumGenesisGeneratorBuilderArgs {
+    /// config file to read, format detected by extension (.env, .yaml, .yml, .toml, .json)
+    #[clap(long, global = true, default_value = "defaults.env")]
+    pub config: PathBuf,
+    /// overrides a config key, takes precedence over the config file and environment
+    #[clap(long = "set", global = true, value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+    /// EL clients to generate genesis files for, same as --set EL_CLIENTS=...
+    #[clap(long, global = true, value_delimiter = ',')]
+    pub el_clients: Vec<String>,
+    /// CL clients to generate config for, same as --set CL_CLIENTS=...
+    #[clap(long, global = true, value_delimiter = ',')]
+    pub cl_clients: Vec<String>,
+    /// seeds the randomness of generated files, same as --set SEED=...
+    #[clap(long, global = true)]
+    pub seed: Option<u64>,
     #[clap(subcommand)]
     pub action: ActionType,
 }
@@ -10,11 +29,84 @@ pub struct EthereumGenesisGeneratorBuilderArgs {
 #[derive(Debug, Subcommand)]
 pub enum ActionType {
     /// creates  
-    EL,
+    EL {
+        /// also packs the metadata folder into this .tar.gz or .tar.zst archive
+        #[clap(long)]
+        archive: Option<PathBuf>,
+    },
     /// creates consensus layer config files to run testnet
-    CL,
+    CL {
+        /// also packs the metadata folder into this .tar.gz or .tar.zst archive
+        #[clap(long)]
+        archive: Option<PathBuf>,
+    },
     /// creates all configuration files required to run a testnet
-    All,
+    All {
+        /// also packs the metadata folder into this .tar.gz or .tar.zst archive
+        #[clap(long)]
+        archive: Option<PathBuf>,
+    },
+    /// prints every resolved config key and where its value came from
+    Explain,
+    /// writes the fully evaluated config, including computed values, so it can be replayed
+    Resolve {
+        /// output format
+        #[clap(long, value_enum, default_value = "json")]
+        format: ResolvedFormat,
+        /// file to write to instead of stdout
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+    /// unpacks an archive created with --archive, checking its files against manifest.json
+    Extract {
+        /// .tar.gz or .tar.zst archive to unpack
+        archive: PathBuf,
+        /// folder to unpack into, must not exist yet [default: /tmp/metadata]
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+    /// describes a genesis.json, chainspec.json, besu.json or genesis.ssz file
+    Inspect {
+        /// file to inspect, EL format detected by file name or contents
+        file: PathBuf,
+    },
+    /// converts an EL genesis file to the format another client reads
+    Convert {
+        /// client the input genesis is written for: geth, reth, erigon, besu or nethermind
+        #[clap(long)]
+        from: String,
+        /// client to convert for: geth, reth, erigon, besu or nethermind
+        #[clap(long)]
+        to: String,
+        /// genesis file to convert
+        input: PathBuf,
+        /// file to write to instead of stdout
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+    /// checks that the EL, CL and deposit contract files of a metadata folder agree
+    Verify {
+        /// metadata folder to check
+        metadata_dir: PathBuf,
+    },
+    /// lists what changes between two metadata folders, field by field
+    Diff {
+        /// metadata folder to compare from
+        dir_a: PathBuf,
+        /// metadata folder to compare to
+        dir_b: PathBuf,
+    },
+    /// prints the epoch, slot and time each configured fork activates at
+    Timeline,
+    /// prints the start time of an epoch
+    EpochToTime {
+        epoch: u64,
+    },
+    /// prints the epoch a Unix timestamp or UTC date falls in
+    TimeToEpoch {
+        /// e.g. 1715688000 or 2024-05-14T14:00:00Z
+        time: String,
+    },
     // pkg-builder version
     Version
 }
@@ -0,0 +1,52 @@
+use crate::el::genesis_config::GenesisConfig;
+
+/// Files a CL client reads from its network folder besides `config.yaml` and `genesis.ssz`.
+/// Both hold the block the deposit contract was deployed in, which is genesis because the
+/// deposit contract is part of the genesis alloc.
+const CLIENT_FILES: &[(&str, &str)] = &[
+    ("lighthouse", "deploy_block.txt"),
+    ("nimbus", "deposit_contract_block.txt"),
+];
+
+/// The files of the CL clients selected by `genesis_config`, of every client if none is.
+pub fn cl_client_files(genesis_config: &GenesisConfig) -> Vec<(&'static str, String)> {
+    let selected = |client: &str| {
+        genesis_config.cl_clients.is_empty()
+            || genesis_config
+                .cl_clients
+                .iter()
+                .any(|selected| selected == client)
+    };
+    CLIENT_FILES
+        .iter()
+        .filter(|(client, _)| selected(client))
+        .map(|(_, file)| (*file, "0\n".to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writes_files_of_selected_clients() {
+        let files = |cl_clients: &[&str]| {
+            let genesis_config = GenesisConfig {
+                cl_clients: cl_clients.iter().map(|client| client.to_string()).collect(),
+                ..Default::default()
+            };
+            cl_client_files(&genesis_config)
+                .into_iter()
+                .map(|(file, _)| file)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            files(&[]),
+            ["deploy_block.txt", "deposit_contract_block.txt"]
+        );
+        assert_eq!(files(&["lighthouse", "teku"]), ["deploy_block.txt"]);
+        assert_eq!(files(&["nimbus"]), ["deposit_contract_block.txt"]);
+        assert!(files(&["teku", "prysm"]).is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt::Write;
+
+use crate::el::{consensus_params::FAR_FUTURE_EPOCH, genesis_config::GenesisConfig};
+
+pub const CL_CONFIG_FILE: &str = "config.yaml";
+
+/// Entries of the CL `config.yaml` for `genesis_config`, in the order of the upstream template.
+pub fn cl_config(genesis_config: &GenesisConfig) -> Vec<(&'static str, String)> {
+    let consensus = &genesis_config.consensus;
+    let epoch = |epoch: Option<u64>| epoch.unwrap_or(FAR_FUTURE_EPOCH).to_string();
+    vec![
+        ("PRESET_BASE", genesis_config.preset_base.clone()),
+        ("CONFIG_NAME", "testnet".to_string()),
+        // Genesis
+        (
+            "MIN_GENESIS_ACTIVE_VALIDATOR_COUNT",
+            consensus.number_of_validators.to_string(),
+        ),
+        (
+            "MIN_GENESIS_TIME",
+            genesis_config.genesis_timestamp.to_string(),
+        ),
+        (
+            "GENESIS_FORK_VERSION",
+            consensus.genesis_fork_version.to_string(),
+        ),
+        ("GENESIS_DELAY", genesis_config.genesis_delay.to_string()),
+        // Forking
+        (
+            "ALTAIR_FORK_VERSION",
+            consensus.altair_fork_version.to_string(),
+        ),
+        ("ALTAIR_FORK_EPOCH", "0".to_string()),
+        (
+            "BELLATRIX_FORK_VERSION",
+            consensus.bellatrix_fork_version.to_string(),
+        ),
+        ("BELLATRIX_FORK_EPOCH", "0".to_string()),
+        ("TERMINAL_TOTAL_DIFFICULTY", "0".to_string()),
+        (
+            "CAPELLA_FORK_VERSION",
+            consensus.capella_fork_version.to_string(),
+        ),
+        (
+            "CAPELLA_FORK_EPOCH",
+            epoch(genesis_config.capella_fork_epoch),
+        ),
+        (
+            "DENEB_FORK_VERSION",
+            consensus.deneb_fork_version.to_string(),
+        ),
+        ("DENEB_FORK_EPOCH", epoch(genesis_config.deneb_fork_epoch)),
+        (
+            "ELECTRA_FORK_VERSION",
+            consensus.electra_fork_version.to_string(),
+        ),
+        (
+            "ELECTRA_FORK_EPOCH",
+            epoch(genesis_config.electra_fork_epoch),
+        ),
+        (
+            "EIP7594_FORK_VERSION",
+            consensus.eip7594_fork_version.to_string(),
+        ),
+        (
+            "EIP7594_FORK_EPOCH",
+            consensus.eip7594_fork_epoch.to_string(),
+        ),
+        // Time parameters
+        (
+            "SECONDS_PER_SLOT",
+            genesis_config.slot_duration_in_seconds.to_string(),
+        ),
+        (
+            "SECONDS_PER_ETH1_BLOCK",
+            genesis_config.slot_duration_in_seconds.to_string(),
+        ),
+        (
+            "MIN_VALIDATOR_WITHDRAWABILITY_DELAY",
+            consensus.min_validator_withdrawability_delay.to_string(),
+        ),
+        (
+            "SHARD_COMMITTEE_PERIOD",
+            consensus.shard_committee_period.to_string(),
+        ),
+        (
+            "ETH1_FOLLOW_DISTANCE",
+            consensus.eth1_follow_distance.to_string(),
+        ),
+        // Validator cycle
+        ("EJECTION_BALANCE", consensus.ejection_balance.to_string()),
+        ("MIN_PER_EPOCH_CHURN_LIMIT", "4".to_string()),
+        (
+            "CHURN_LIMIT_QUOTIENT",
+            consensus.churn_limit_quotient.to_string(),
+        ),
+        (
+            "MAX_PER_EPOCH_ACTIVATION_CHURN_LIMIT",
+            consensus.max_per_epoch_activation_churn_limit.to_string(),
+        ),
+        // Deposit contract
+        ("DEPOSIT_CHAIN_ID", genesis_config.chain_id.to_string()),
+        ("DEPOSIT_NETWORK_ID", genesis_config.chain_id.to_string()),
+        (
+            "DEPOSIT_CONTRACT_ADDRESS",
+            genesis_config.deposit_contract_address.clone(),
+        ),
+        // Networking
+        (
+            "MIN_EPOCHS_FOR_BLOCK_REQUESTS",
+            consensus.min_epochs_for_block_requests.to_string(),
+        ),
+        (
+            "MAX_BLOBS_PER_BLOCK",
+            consensus.max_blobs_per_block.to_string(),
+        ),
+        // PeerDAS
+        ("SAMPLES_PER_SLOT", consensus.samples_per_slot.to_string()),
+        (
+            "CUSTODY_REQUIREMENT",
+            consensus.custody_requirement.to_string(),
+        ),
+        (
+            "DATA_COLUMN_SIDECAR_SUBNET_COUNT",
+            consensus.data_column_sidecar_subnet_count.to_string(),
+        ),
+        (
+            "TARGET_NUMBER_OF_PEERS",
+            consensus.target_number_of_peers.to_string(),
+        ),
+    ]
+}
+
+/// Renders `entries` as YAML. Hex values are quoted so YAML readers keep them as strings.
+pub fn render_cl_config(entries: &[(&str, String)]) -> String {
+    let mut yaml = String::new();
+    for (key, value) in entries {
+        if value.starts_with("0x") {
+            writeln!(yaml, "{}: '{}'", key, value).unwrap();
+        } else {
+            writeln!(yaml, "{}: {}", key, value).unwrap();
+        }
+    }
+    yaml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::read_cl_config;
+    use std::fs;
+
+    #[test]
+    fn test_config_yaml_reads_back_consensus_params() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut genesis_config = GenesisConfig {
+            preset_base: "minimal".to_string(),
+            chain_id: 3151908,
+            deposit_contract_address: "0x4242424242424242424242424242424242424242".to_string(),
+            slot_duration_in_seconds: 6,
+            capella_fork_epoch: Some(0),
+            ..Default::default()
+        };
+        genesis_config.consensus.number_of_validators = 128;
+        genesis_config.consensus.churn_limit_quotient = 32;
+
+        let path = dir.path().join(CL_CONFIG_FILE);
+        fs::write(&path, render_cl_config(&cl_config(&genesis_config))).unwrap();
+
+        let cl_config = read_cl_config(&path).unwrap();
+        assert_eq!(cl_config["PRESET_BASE"], "minimal");
+        assert_eq!(cl_config["MIN_GENESIS_ACTIVE_VALIDATOR_COUNT"], "128");
+        assert_eq!(cl_config["CHURN_LIMIT_QUOTIENT"], "32");
+        assert_eq!(cl_config["GENESIS_FORK_VERSION"], "0x10000038");
+        assert_eq!(
+            cl_config["DEPOSIT_CONTRACT_ADDRESS"],
+            "0x4242424242424242424242424242424242424242"
+        );
+        assert_eq!(cl_config["CAPELLA_FORK_EPOCH"], "0");
+        assert_eq!(cl_config["DENEB_FORK_EPOCH"], FAR_FUTURE_EPOCH.to_string());
+        assert_eq!(cl_config["SECONDS_PER_SLOT"], "6");
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{bail, Context, Result};
+use ethers::types::{Bytes, H256};
+use sha2::{Digest, Sha256};
+use std::{fs, path::Path};
+
+use crate::el::consensus_params::ForkVersion;
+
+/// SSZ size of a `Validator` record.
+const VALIDATOR_SIZE: usize = 121;
+
+/// Vector lengths of the preset that determine where the fields of a `BeaconState` start.
+struct Preset {
+    slots_per_historical_root: usize,
+    epochs_per_historical_vector: usize,
+    epochs_per_slashings_vector: usize,
+    sync_committee_size: usize,
+}
+
+impl Preset {
+    fn from_base(preset_base: &str) -> Self {
+        if preset_base == "mainnet" {
+            Preset {
+                slots_per_historical_root: 8192,
+                epochs_per_historical_vector: 65536,
+                epochs_per_slashings_vector: 8192,
+                sync_committee_size: 512,
+            }
+        } else {
+            Preset {
+                slots_per_historical_root: 64,
+                epochs_per_historical_vector: 64,
+                epochs_per_slashings_vector: 64,
+                sync_committee_size: 32,
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Validator {
+    pub pubkey: Bytes,
+    pub withdrawal_credentials: H256,
+    pub effective_balance: u64,
+}
+
+/// The parts of the execution payload header that tie the CL genesis to an EL genesis block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionPayloadHeader {
+    pub block_hash: H256,
+    pub block_number: u64,
+    pub timestamp: u64,
+}
+
+/// The fields of an SSZ `BeaconState` (`genesis.ssz`) that describe a network, read at their
+/// fixed offsets instead of decoding the whole state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenesisState {
+    pub genesis_time: u64,
+    pub genesis_validators_root: H256,
+    pub fork_version: ForkVersion,
+    pub latest_block_body_root: H256,
+    pub validators: Vec<Validator>,
+    /// Present from Bellatrix on.
+    pub execution_payload_header: Option<ExecutionPayloadHeader>,
+}
+
+impl GenesisState {
+    pub fn read<P: AsRef<Path>>(path: P, preset_base: &str) -> Result<Self> {
+        let path = path.as_ref();
+        let ssz = fs::read(path).with_context(|| format!("Cannot read {}", path.display()))?;
+        GenesisState::from_ssz(&ssz, preset_base).with_context(|| {
+            format!(
+                "Cannot parse {} as {} beacon state",
+                path.display(),
+                preset_base
+            )
+        })
+    }
+
+    pub fn from_ssz(ssz: &[u8], preset_base: &str) -> Result<Self> {
+        let preset = Preset::from_base(preset_base);
+        let reader = SszReader(ssz);
+
+        // genesis_time, genesis_validators_root, slot, fork, latest_block_header
+        let fork = 8 + 32 + 8;
+        let latest_block_header = fork + 16;
+        let block_roots = latest_block_header + 112;
+        let historical_roots = block_roots + 2 * preset.slots_per_historical_root * 32;
+        let validators = historical_roots + 4 + 72 + 4 + 8;
+        let balances = validators + 4;
+        let randao_mixes = balances + 4;
+        let slashings = randao_mixes + preset.epochs_per_historical_vector * 32;
+        let participation = slashings + preset.epochs_per_slashings_vector * 8;
+        let inactivity_scores = participation + 4 + 4 + 1 + 3 * 40;
+        let sync_committees = inactivity_scores + 4;
+        let execution_payload_header = sync_committees + 2 * (preset.sync_committee_size + 1) * 48;
+
+        let validators_start = reader.offset(validators)?;
+        let validators_end = reader.offset(balances)?;
+        if validators_end < validators_start
+            || (validators_end - validators_start) % VALIDATOR_SIZE != 0
+        {
+            bail!("validator list has an invalid length");
+        }
+        let validators = (validators_start..validators_end)
+            .step_by(VALIDATOR_SIZE)
+            .map(|start| {
+                Ok(Validator {
+                    pubkey: Bytes::from(reader.bytes(start, 48)?.to_vec()),
+                    withdrawal_credentials: reader.h256(start + 48)?,
+                    effective_balance: reader.u64(start + 80)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        // The first variable-size field starts right after the fixed part, which only reaches
+        // past the payload header offset from Bellatrix on.
+        let fixed_size = reader.offset(historical_roots)?;
+        let execution_payload_header = if fixed_size >= execution_payload_header + 4 {
+            let start = reader.offset(execution_payload_header)?;
+            Some(ExecutionPayloadHeader {
+                block_number: reader.u64(start + 404)?,
+                timestamp: reader.u64(start + 428)?,
+                block_hash: reader.h256(start + 472)?,
+            })
+        } else {
+            None
+        };
+
+        let mut fork_version = [0u8; 4];
+        fork_version.copy_from_slice(reader.bytes(fork + 4, 4)?);
+        Ok(GenesisState {
+            genesis_time: reader.u64(0)?,
+            genesis_validators_root: reader.h256(8)?,
+            fork_version: ForkVersion(fork_version),
+            latest_block_body_root: reader.h256(latest_block_header + 80)?,
+            validators,
+            execution_payload_header,
+        })
+    }
+}
+
+/// `compute_fork_digest` of the consensus specs: the first 4 bytes of the `ForkData` root.
+pub fn fork_digest(fork_version: ForkVersion, genesis_validators_root: H256) -> [u8; 4] {
+    let mut fork_data = [0u8; 64];
+    fork_data[..4].copy_from_slice(&fork_version.0);
+    fork_data[32..].copy_from_slice(genesis_validators_root.as_bytes());
+    let mut digest = [0u8; 4];
+    digest.copy_from_slice(&Sha256::digest(fork_data)[..4]);
+    digest
+}
+
+struct SszReader<'a>(&'a [u8]);
+
+impl SszReader<'_> {
+    fn bytes(&self, start: usize, len: usize) -> Result<&[u8]> {
+        self.0
+            .get(start..start + len)
+            .with_context(|| format!("state ends before byte {}", start + len))
+    }
+
+    fn u64(&self, start: usize) -> Result<u64> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.bytes(start, 8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn h256(&self, start: usize) -> Result<H256> {
+        Ok(H256::from_slice(self.bytes(start, 32)?))
+    }
+
+    /// Start of a variable-size field, whose 4-byte offset is stored at `start`.
+    fn offset(&self, start: usize) -> Result<usize> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(self.bytes(start, 4)?);
+        let offset = u32::from_le_bytes(bytes) as usize;
+        if offset > self.0.len() {
+            bail!("offset {} points past the end of the state", offset);
+        }
+        Ok(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_fields_of_minimal_state() {
+        // Fixed part of a minimal-preset Deneb state followed by two validators and a payload
+        // header; unlisted fields stay zero.
+        let randao_mixes = 8 + 32 + 8 + 16 + 112 + 2 * 64 * 32 + 4 + 72 + 4 + 8 + 4 + 4;
+        let payload_offset = randao_mixes + 64 * 32 + 64 * 8 + 4 + 4 + 1 + 3 * 40 + 4 + 2 * 33 * 48;
+        let fixed = payload_offset + 4 + 8 + 8 + 4;
+        let validators_start = fixed;
+        let balances_start = validators_start + 2 * VALIDATOR_SIZE;
+        let payload_start = balances_start + 16;
+        let mut ssz = vec![0u8; payload_start + 584];
+
+        ssz[0..8].copy_from_slice(&1_700_000_000u64.to_le_bytes());
+        ssz[8..40].copy_from_slice(&[0xaa; 32]);
+        ssz[52..56].copy_from_slice(&[0x10, 0, 0, 0x38]);
+        let historical_roots_offset = 8 + 32 + 8 + 16 + 112 + 2 * 64 * 32;
+        ssz[historical_roots_offset..historical_roots_offset + 4]
+            .copy_from_slice(&(fixed as u32).to_le_bytes());
+        let validators_offset = historical_roots_offset + 4 + 72 + 4 + 8;
+        ssz[validators_offset..validators_offset + 4]
+            .copy_from_slice(&(validators_start as u32).to_le_bytes());
+        ssz[validators_offset + 4..validators_offset + 8]
+            .copy_from_slice(&(balances_start as u32).to_le_bytes());
+        ssz[payload_offset..payload_offset + 4]
+            .copy_from_slice(&(payload_start as u32).to_le_bytes());
+        for index in 0..2 {
+            let start = validators_start + index * VALIDATOR_SIZE;
+            ssz[start] = index as u8 + 1;
+            ssz[start + 48] = 0x01;
+            ssz[start + 80..start + 88].copy_from_slice(&32_000_000_000u64.to_le_bytes());
+        }
+        ssz[payload_start + 428..payload_start + 436]
+            .copy_from_slice(&1_700_000_000u64.to_le_bytes());
+        ssz[payload_start + 472..payload_start + 504].copy_from_slice(&[0xbb; 32]);
+
+        let state = GenesisState::from_ssz(&ssz, "minimal").unwrap();
+
+        assert_eq!(state.genesis_time, 1_700_000_000);
+        assert_eq!(state.genesis_validators_root, H256::repeat_byte(0xaa));
+        assert_eq!(state.fork_version.to_string(), "0x10000038");
+        assert_eq!(state.validators.len(), 2);
+        assert_eq!(state.validators[1].pubkey[0], 2);
+        assert_eq!(state.validators[1].effective_balance, 32_000_000_000);
+        let header = state.execution_payload_header.unwrap();
+        assert_eq!(header.block_hash, H256::repeat_byte(0xbb));
+        assert_eq!(header.timestamp, 1_700_000_000);
+        assert!(GenesisState::from_ssz(&ssz[..100], "minimal").is_err());
+    }
+
+    #[test]
+    fn test_fork_digests_match_mainnet() {
+        let genesis_validators_root: H256 =
+            "0x4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95"
+                .parse()
+                .unwrap();
+        let digests: Vec<_> = (0..5u8)
+            .map(|fork| fork_digest(ForkVersion([fork, 0, 0, 0]), genesis_validators_root))
+            .collect();
+        assert_eq!(
+            digests,
+            [
+                [0xb5, 0x30, 0x3f, 0x2a],
+                [0xaf, 0xca, 0xab, 0xa0],
+                [0x4a, 0x26, 0xc5, 0x8b],
+                [0xbb, 0xa4, 0xda, 0x96],
+                [0x6a, 0x95, 0xa1, 0xa9],
+            ]
+        );
+    }
+}
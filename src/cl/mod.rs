@@ -0,0 +1,3 @@
+pub mod client_files;
+pub mod config;
+pub mod genesis_state;
@@ -1,32 +1,176 @@
-use std::path::PathBuf;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use crate::{
+    archive::{create_archive, extract_archive},
     args::{ActionType, EthereumGenesisGeneratorBuilderArgs},
-    defaults_env::read_defaults,
-    generation::{gen_cl_config, gen_el_config, gen_shared_files},
+    convert::convert,
+    diff::diff_bundles,
+    generation::{
+        gen_cl_config, gen_el_config, gen_manifest, gen_shared_files, get_genesis_config,
+    },
+    inspect::inspect,
+    layered_config::LayeredConfig,
+    resolve::{render, resolve},
+    staging::StagingDir,
+    timeline::{parse_utc_time, timeline, EpochTime},
+    verify::verify,
 };
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 
+const METADATA_FOLDER: &str = "/tmp/metadata";
+
+fn load_config(args: &EthereumGenesisGeneratorBuilderArgs) -> Result<LayeredConfig> {
+    let config = LayeredConfig::new()
+        .with_defaults()
+        .with_file(&args.config)
+        .context("Could not parse context file")?
+        .with_env()
+        .with_overrides(&args.set)?
+        .with_overrides(&flag_overrides(args))?;
+    Ok(config)
+}
+
+/// `--el-clients`, `--cl-clients` and `--seed` as `KEY=VALUE` overrides.
+fn flag_overrides(args: &EthereumGenesisGeneratorBuilderArgs) -> Vec<String> {
+    let mut overrides = Vec::new();
+    if !args.el_clients.is_empty() {
+        overrides.push(format!("EL_CLIENTS={}", args.el_clients.join(",")));
+    }
+    if !args.cl_clients.is_empty() {
+        overrides.push(format!("CL_CLIENTS={}", args.cl_clients.join(",")));
+    }
+    if let Some(seed) = args.seed {
+        overrides.push(format!("SEED={}", seed));
+    }
+    overrides
+}
+
+/// Moves the staged files into `metadata_folder` and packs it into `archive` if requested.
+fn commit(staging: StagingDir, metadata_folder: &Path, archive: &Option<PathBuf>) -> Result<()> {
+    staging.commit()?;
+    if let Some(archive) = archive {
+        create_archive(metadata_folder, archive)?;
+    }
+    Ok(())
+}
+
 pub fn run_cli() -> Result<()> {
     let args = EthereumGenesisGeneratorBuilderArgs::try_parse()
         .context("Could not parse command line arguments")?;
-    match args.action {
-        ActionType::EL => {
-            let context = read_defaults("defaults.env").context("Could not parse context file")?;
-            gen_el_config(context, PathBuf::from("/tmp/metadata"))?;
+    run(&args, Path::new(METADATA_FOLDER))
+}
+
+/// Runs the action of `args`, generating into and extracting to `metadata_folder`.
+fn run(args: &EthereumGenesisGeneratorBuilderArgs, metadata_folder: &Path) -> Result<()> {
+    match &args.action {
+        ActionType::EL { archive } => {
+            let context = load_config(args)?.context();
+            let staging = StagingDir::begin(metadata_folder)?;
+            gen_el_config(context.clone(), staging.path().to_path_buf())?;
+            gen_manifest(context, staging.path().to_path_buf())?;
+            commit(staging, metadata_folder, archive)
+        }
+        ActionType::All { archive } => {
+            let context = load_config(args)?.context();
+            let staging = StagingDir::begin(metadata_folder)?;
+            gen_el_config(context.clone(), staging.path().to_path_buf())?;
+            gen_cl_config(context.clone(), staging.path().to_path_buf())?;
+            gen_shared_files(context.clone(), staging.path().to_path_buf())?;
+            gen_manifest(context, staging.path().to_path_buf())?;
+            commit(staging, metadata_folder, archive)
+        }
+        ActionType::CL { archive } => {
+            let context = load_config(args)?.context();
+            let staging = StagingDir::begin(metadata_folder)?;
+            gen_cl_config(context.clone(), staging.path().to_path_buf())?;
+            gen_manifest(context, staging.path().to_path_buf())?;
+            commit(staging, metadata_folder, archive)
+        }
+        ActionType::Explain => {
+            for (key, resolved) in load_config(args)?.entries() {
+                println!("{}={}  # {}", key, resolved.value, resolved.source);
+            }
+            Ok(())
+        }
+        ActionType::Resolve { format, output } => {
+            let genesis_config = get_genesis_config(load_config(args)?.context())?;
+            let resolved = render(&resolve(&genesis_config)?, *format)?;
+            match output {
+                Some(output) => fs::write(output, resolved)
+                    .with_context(|| format!("Cannot write {}", output.display()))?,
+                None => print!("{}", resolved),
+            }
+            Ok(())
+        }
+        ActionType::Extract { archive, output } => {
+            let output = output.as_deref().unwrap_or(metadata_folder);
+            extract_archive(archive, output)
+        }
+        ActionType::Inspect { file } => {
+            let genesis_config = get_genesis_config(load_config(args)?.context())?;
+            print!("{}", inspect(file, &genesis_config)?);
+            Ok(())
+        }
+        ActionType::Convert {
+            from,
+            to,
+            input,
+            output,
+        } => {
+            let conversion = convert(input, from, to)?;
+            for field in &conversion.dropped {
+                eprintln!(
+                    "warning: {} cannot be expressed in the genesis format of {}, dropped",
+                    field, to
+                );
+            }
+            match output {
+                Some(output) => fs::write(output, &conversion.output)
+                    .with_context(|| format!("Cannot write {}", output.display()))?,
+                None => println!("{}", String::from_utf8_lossy(&conversion.output)),
+            }
+            Ok(())
+        }
+        ActionType::Verify { metadata_dir } => {
+            let report = verify(metadata_dir)?;
+            print!("{}", report);
+            if !report.mismatches.is_empty() {
+                bail!(
+                    "{} mismatches in {}",
+                    report.mismatches.len(),
+                    metadata_dir.display()
+                );
+            }
             Ok(())
         }
-        ActionType::All => {
-            let context = read_defaults("defaults.env").context("Could not parse context file")?;
-            gen_el_config(context.clone(), PathBuf::from("/tmp/metadata"))?;
-            gen_cl_config(context.clone(), PathBuf::from("/tmp/metadata"))?;
-            gen_shared_files(context, PathBuf::from("/tmp/metadata"))?;
+        ActionType::Diff { dir_a, dir_b } => {
+            let changes = diff_bundles(dir_a, dir_b)?;
+            if changes.is_empty() {
+                println!("no differences");
+            }
+            for change in changes {
+                println!("{}", change);
+            }
             Ok(())
         }
-        ActionType::CL => {
-            let context = read_defaults("defaults.env").context("Could not parse context file")?;
-            gen_cl_config(context, PathBuf::from("/tmp/metadata"))?;
+        ActionType::Timeline => {
+            let genesis_config = get_genesis_config(load_config(args)?.context())?;
+            print!("{}", timeline(&genesis_config));
+            Ok(())
+        }
+        ActionType::EpochToTime { epoch } => {
+            let genesis_config = get_genesis_config(load_config(args)?.context())?;
+            println!("{}", EpochTime::at_epoch(&genesis_config, *epoch)?);
+            Ok(())
+        }
+        ActionType::TimeToEpoch { time } => {
+            let genesis_config = get_genesis_config(load_config(args)?.context())?;
+            let time = parse_utc_time(time)?;
+            println!("{}", EpochTime::containing(&genesis_config, time)?);
             Ok(())
         }
         ActionType::Version => {
@@ -35,3 +179,130 @@ pub fn run_cli() -> Result<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::fixtures::geth_genesis;
+    use crate::manifest::Manifest;
+    use serde_json::json;
+    use serial_test::serial;
+    use std::env;
+
+    /// Runs `command` with a devnet config in a folder whose `../data/devnet` holds the templates
+    /// of the built-in EL formats, as the generator expects.
+    fn run_in(dir: &Path, command: &[&str]) -> Result<()> {
+        let templates = dir.join("data/devnet");
+        fs::create_dir_all(&templates)?;
+        let template = geth_genesis(0, json!({}), json!({})).to_string();
+        for file in [
+            "geth_genesis.json",
+            "besu_genesis.json",
+            "chainspec_genesis.json",
+        ] {
+            fs::write(templates.join(file), &template)?;
+        }
+        let work = dir.join("work");
+        fs::create_dir_all(&work)?;
+        let config = dir.join("values.env");
+        let values = [
+            ("PRESET_BASE", "minimal"),
+            ("CHAIN_ID", "1337"),
+            (
+                "DEPOSIT_CONTRACT_ADDRESS",
+                "0x4242424242424242424242424242424242424242",
+            ),
+            (
+                "EL_AND_CL_MNEMONIC",
+                "test test test test test test test test test test test junk",
+            ),
+            ("EL_PREMINE_COUNT", "1"),
+            ("GENESIS_TIMESTAMP", "1700000000"),
+            ("GENESIS_DELAY", "60"),
+            ("SLOT_DURATION_IN_SECONDS", "6"),
+            ("CAPELLA_FORK_EPOCH", "0"),
+            ("DENEB_FORK_EPOCH", "0"),
+            ("SEED", "7"),
+        ];
+        let values: String = values
+            .iter()
+            .map(|(key, value)| format!("export {}=\"{}\"\n", key, value))
+            .collect();
+        fs::write(&config, values)?;
+
+        let mut argv = vec!["generator", "--config", config.to_str().unwrap()];
+        argv.extend(command);
+        let args = EthereumGenesisGeneratorBuilderArgs::try_parse_from(argv)?;
+        let cwd = env::current_dir()?;
+        env::set_current_dir(&work)?;
+        let result = run(&args, &dir.join("metadata"));
+        env::set_current_dir(cwd)?;
+        result
+    }
+
+    fn manifest_paths(metadata: &Path) -> Vec<String> {
+        let manifest = Manifest::read(metadata).unwrap();
+        manifest.verify_files(metadata).unwrap();
+        manifest.files.into_iter().map(|file| file.path).collect()
+    }
+
+    #[test]
+    #[serial]
+    fn test_cl_after_el_keeps_el_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = dir.path().join("metadata");
+
+        run_in(dir.path(), &["el"]).unwrap();
+        run_in(dir.path(), &["cl"]).unwrap();
+
+        for file in ["genesis.json", "chainspec.json", "besu.json", "config.yaml"] {
+            assert!(metadata.join(file).exists(), "{} missing", file);
+        }
+        let paths = manifest_paths(&metadata);
+        assert!(paths.contains(&"genesis.json".to_string()), "{:?}", paths);
+        assert!(paths.contains(&"config.yaml".to_string()), "{:?}", paths);
+    }
+
+    #[test]
+    #[serial]
+    fn test_cl_clients_select_client_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = dir.path().join("metadata");
+
+        run_in(dir.path(), &["cl", "--cl-clients", "teku"]).unwrap();
+        assert!(metadata.join("config.yaml").exists());
+        assert!(!metadata.join("deploy_block.txt").exists());
+
+        run_in(dir.path(), &["cl", "--cl-clients", "lighthouse"]).unwrap();
+        assert_eq!(
+            fs::read_to_string(metadata.join("deploy_block.txt")).unwrap(),
+            "0\n"
+        );
+        assert!(!metadata.join("deposit_contract_block.txt").exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test_all_writes_a_consistent_metadata_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = dir.path().join("metadata");
+
+        run_in(dir.path(), &["all"]).unwrap();
+
+        let paths = manifest_paths(&metadata);
+        for file in [
+            "genesis.json",
+            "chainspec.json",
+            "besu.json",
+            "accounts.json",
+            "config.yaml",
+            "deploy_block.txt",
+            "deposit_contract.txt",
+            "deposit_contract_block_hash.txt",
+        ] {
+            assert!(paths.contains(&file.to_string()), "{} missing", file);
+        }
+        let report = verify(&metadata).unwrap();
+        assert!(report.mismatches.is_empty(), "{}", report);
+    }
+}
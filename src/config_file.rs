@@ -0,0 +1,302 @@
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    defaults_env::{read_defaults, read_defaults_with_lines},
+    el::{format_registry::Fork, genesis_config::ForkSchedule},
+    timeline::format_rfc3339,
+};
+
+/// Keys whose nested value is handed to `GenesisConfigBuilder` as a JSON document
+/// instead of being flattened into separate keys.
+const JSON_VALUED_KEYS: [&str; 3] = [
+    "EL_PREMINE_PATHS",
+    "EL_PREMINE_ADDRS",
+    "ADDITIONAL_PRELOADED_CONTRACTS",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Env,
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match extension.as_str() {
+            "env" => Ok(ConfigFormat::Env),
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            "toml" => Ok(ConfigFormat::Toml),
+            "json" => Ok(ConfigFormat::Json),
+            _ => bail!(
+                "Cannot detect config format of {}, expected .env, .yaml, .yml, .toml or .json",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Reads a config file in any supported format into the flat key/value context
+/// consumed by `GenesisConfigBuilder::from_hashmap`.
+pub fn read_config<P: AsRef<Path>>(path: P) -> Result<HashMap<String, String>> {
+    let path = path.as_ref();
+    let format = ConfigFormat::from_path(path)?;
+    if format == ConfigFormat::Env {
+        return read_defaults(path);
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Cannot read file {}", path.display()))?;
+    parse_config(&content, format)
+        .with_context(|| format!("Could not parse config file {}", path.display()))
+}
+
+/// Reads a config file like `read_config`, keeping the line each value was defined on
+/// where the format allows it (currently only env files).
+pub fn read_config_with_lines<P: AsRef<Path>>(
+    path: P,
+) -> Result<HashMap<String, (String, Option<usize>)>> {
+    let path = path.as_ref();
+    if ConfigFormat::from_path(path)? == ConfigFormat::Env {
+        let lines = read_defaults_with_lines(path)?;
+        return Ok(lines
+            .into_iter()
+            .map(|(key, (value, line))| (key, (value, Some(line))))
+            .collect());
+    }
+    let context = read_config(path)?;
+    Ok(context
+        .into_iter()
+        .map(|(key, value)| (key, (value, None)))
+        .collect())
+}
+
+/// Parses a structured config document. Nested tables are flattened by joining keys with `_`,
+/// so `el_premine: {count: 100}` becomes `EL_PREMINE_COUNT`. A `forks` table maps fork names,
+/// the CL or EL name of a `Fork` such as `deneb` or `cancun`, to
+/// epochs (`forks: {electra: 10}` or `forks: {electra: {epoch: 10}}` both set `ELECTRA_FORK_EPOCH`)
+/// or to times (`forks: {electra: {time: "+2h"}}`), which are rounded up to an epoch. A time is a
+/// UTC date, a Unix timestamp or an offset after CL genesis, never an epoch.
+pub fn parse_config(content: &str, format: ConfigFormat) -> Result<HashMap<String, String>> {
+    let document: Value = match format {
+        ConfigFormat::Yaml => serde_yaml::from_str(content)?,
+        ConfigFormat::Toml => toml_to_json(toml::from_str(content)?),
+        ConfigFormat::Json => serde_json::from_str(content)?,
+        ConfigFormat::Env => bail!("env files are parsed by read_defaults"),
+    };
+    let Value::Object(entries) = document else {
+        bail!("Config document must be a table of keys");
+    };
+
+    let mut context = HashMap::new();
+    for (key, value) in entries {
+        let key = key.to_uppercase();
+        if key == "FORKS" {
+            flatten_forks(value, &mut context)?;
+        } else {
+            flatten(key, value, &mut context)?;
+        }
+    }
+    Ok(context)
+}
+
+fn flatten(key: String, value: Value, context: &mut HashMap<String, String>) -> Result<()> {
+    match value {
+        Value::Object(entries) if !JSON_VALUED_KEYS.contains(&key.as_str()) => {
+            for (nested_key, nested_value) in entries {
+                let nested_key = format!("{}_{}", key, nested_key.to_uppercase());
+                flatten(nested_key, nested_value, context)?;
+            }
+        }
+        value => {
+            if context
+                .insert(key.clone(), scalar_to_string(value)?)
+                .is_some()
+            {
+                bail!("{} is defined more than once", key);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn flatten_forks(value: Value, context: &mut HashMap<String, String>) -> Result<()> {
+    let Value::Object(forks) = value else {
+        bail!("forks must be a table of fork names");
+    };
+    for (fork, schedule) in forks {
+        let Some(key) = Fork::from_name(&fork).map(|fork| fork.config_key()) else {
+            bail!(
+                "unknown fork {}, known forks: {}",
+                fork,
+                Fork::ALL.map(|fork| fork.cl_name()).join(", ")
+            );
+        };
+        let epoch = match schedule {
+            Value::Object(mut schedule) => {
+                match (schedule.remove("epoch"), schedule.remove("time")) {
+                    (Some(epoch), None) => epoch,
+                    (None, Some(time)) => Value::String(fork_time(&fork, time)?),
+                    (Some(_), Some(_)) => bail!("fork {} has both an epoch and a time", fork),
+                    (None, None) => bail!("fork {} has no epoch or time", fork),
+                }
+            }
+            epoch => epoch,
+        };
+        flatten(key.to_string(), epoch, context)?;
+    }
+    Ok(())
+}
+
+/// Writes a fork time in a form `ForkSchedule::from_str` cannot mistake for an epoch.
+fn fork_time(fork: &str, time: Value) -> Result<String> {
+    let time = scalar_to_string(time)?;
+    let schedule = ForkSchedule::from_time(&time)
+        .with_context(|| format!("Invalid time {} of fork {}", time, fork))?;
+    match schedule {
+        ForkSchedule::AfterGenesis(offset) => Ok(format!("+{}s", offset)),
+        ForkSchedule::At(time) => format_rfc3339(time)
+            .with_context(|| format!("time {} of fork {} is out of range", time, fork)),
+        ForkSchedule::Epoch(_) => unreachable!("from_time never returns an epoch"),
+    }
+}
+
+/// Converts a TOML document to JSON, writing datetimes as strings rather than the private
+/// table serde uses for them.
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(integer) => integer.into(),
+        toml::Value::Float(float) => float.into(),
+        toml::Value::Boolean(boolean) => boolean.into(),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => array.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => table
+            .into_iter()
+            .map(|(key, value)| (key, toml_to_json(value)))
+            .collect(),
+    }
+}
+
+fn scalar_to_string(value: Value) -> Result<String> {
+    Ok(match value {
+        Value::Null => String::new(),
+        Value::String(string) => string,
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(_) | Value::Object(_) => serde_json::to_string(&value)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::config_keys::{check_unknown_keys, ConfigMode};
+
+    #[test]
+    fn test_detects_format_by_extension() {
+        assert_eq!(
+            ConfigFormat::from_path("values.yml").unwrap(),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path("defaults.env").unwrap(),
+            ConfigFormat::Env
+        );
+        assert!(ConfigFormat::from_path("values.ini").is_err());
+    }
+
+    #[test]
+    fn test_parses_nested_yaml() {
+        let content = "
+chain_id: 1337
+preset_base: minimal
+el_premine:
+  count: 1000
+  balance: 1ETH
+el_premine_addrs:
+  \"0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed\":
+    balance: 1ETH
+forks:
+  electra:
+    epoch: 10
+";
+        let context = parse_config(content, ConfigFormat::Yaml).unwrap();
+
+        assert_eq!(context["CHAIN_ID"], "1337");
+        assert_eq!(context["PRESET_BASE"], "minimal");
+        assert_eq!(context["EL_PREMINE_COUNT"], "1000");
+        assert_eq!(context["EL_PREMINE_BALANCE"], "1ETH");
+        assert_eq!(context["ELECTRA_FORK_EPOCH"], "10");
+        let premine_addrs: Value = serde_json::from_str(&context["EL_PREMINE_ADDRS"]).unwrap();
+        assert_eq!(
+            premine_addrs["0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"]["balance"],
+            "1ETH"
+        );
+    }
+
+    #[test]
+    fn test_parses_toml_and_json() {
+        let toml = "CHAIN_ID = 1337\n[forks]\nelectra = 5\n";
+        let context = parse_config(toml, ConfigFormat::Toml).unwrap();
+        assert_eq!(context["CHAIN_ID"], "1337");
+        assert_eq!(context["ELECTRA_FORK_EPOCH"], "5");
+
+        let json = "{\"chain_id\": 1337, \"genesis_delay\": 60}";
+        let context = parse_config(json, ConfigFormat::Json).unwrap();
+        assert_eq!(context["GENESIS_DELAY"], "60");
+    }
+
+    #[test]
+    fn test_fork_times_are_never_read_as_epochs() {
+        let toml = "[forks.electra]\ntime = 2024-05-14T14:00:00Z\n[forks.deneb]\ntime = \"+2h\"\n";
+        let context = parse_config(toml, ConfigFormat::Toml).unwrap();
+        assert_eq!(context["ELECTRA_FORK_EPOCH"], "2024-05-14T14:00:00Z");
+        assert_eq!(context["DENEB_FORK_EPOCH"], "+7200s");
+        assert_eq!(context.len(), 2);
+
+        let yaml = "forks:\n  electra:\n    time: 1715695200\n";
+        let context = parse_config(yaml, ConfigFormat::Yaml).unwrap();
+        assert_eq!(context["ELECTRA_FORK_EPOCH"], "2024-05-14T14:00:00Z");
+        assert_eq!(
+            context["ELECTRA_FORK_EPOCH"]
+                .parse::<ForkSchedule>()
+                .unwrap(),
+            ForkSchedule::At(1_715_695_200)
+        );
+
+        let yaml = "forks:\n  electra:\n    time: 1715695200\n    epoch: 10\n";
+        assert!(parse_config(yaml, ConfigFormat::Yaml).is_err());
+    }
+
+    #[test]
+    fn test_maps_every_fork_name_to_its_config_key() {
+        for fork in Fork::ALL {
+            for name in [fork.cl_name().to_string(), fork.to_string()] {
+                let yaml = format!("forks:\n  {}: 3\n", name);
+                let context = parse_config(&yaml, ConfigFormat::Yaml).unwrap();
+                assert_eq!(context[fork.config_key()], "3", "{}", name);
+                check_unknown_keys(&context, ConfigMode::Strict).unwrap();
+            }
+        }
+
+        let error = parse_config("forks:\n  osaka: 3\n", ConfigFormat::Yaml).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown fork osaka, known forks: capella, deneb, electra, eof"
+        );
+    }
+
+    #[test]
+    fn test_rejects_keys_defined_twice() {
+        let content = "el_premine_count: 1\nel_premine:\n  count: 2\n";
+        assert!(parse_config(content, ConfigFormat::Yaml).is_err());
+    }
+}
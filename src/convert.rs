@@ -0,0 +1,195 @@
+use anyhow::{bail, Context, Result};
+use ethers::types::Address;
+use serde_json::Value;
+use std::{fs, path::Path};
+
+use crate::el::{
+    besu_generation::BesuGenesisConfig, format_registry::FormatRegistry,
+    geth_generation::GethGenesisConfig, nethermind_chainspec::NethermindChainspec,
+    network_genesis::NetworkGenesis, serializabe_to_file::Genesis,
+};
+
+pub struct Conversion {
+    /// The genesis in the target format.
+    pub output: Vec<u8>,
+    /// Fields of the input the target format has no place for, as dotted paths.
+    pub dropped: Vec<String>,
+}
+
+/// Converts the genesis file `input` read by client `from` into the format read by client `to`.
+/// The geth-style formats of geth, reth, erigon and besu and the Nethermind chainspec are
+/// supported. A field is reported as dropped when it does not survive converting the output
+/// back into the format of `from`.
+pub fn convert(input: &Path, from: &str, to: &str) -> Result<Conversion> {
+    let registry = FormatRegistry::default();
+    let from = format_of_client(&registry, from)?;
+    let to = format_of_client(&registry, to)?;
+
+    let source = fs::read(input).with_context(|| format!("Cannot read {}", input.display()))?;
+    let network = read_network(from, &source)
+        .with_context(|| format!("Cannot read {} as {} genesis", input.display(), from))?;
+    let output = write_network(to, network)?;
+    let round_trip = write_network(from, read_network(to, &output)?)?;
+
+    let source: Value = serde_json::from_slice(&source)?;
+    let round_trip: Value = serde_json::from_slice(&round_trip)?;
+    let mut dropped = Vec::new();
+    dropped_fields(&source, &round_trip, "", &mut dropped);
+    Ok(Conversion { output, dropped })
+}
+
+/// Name of the format `client` reads.
+fn format_of_client(registry: &FormatRegistry, client: &str) -> Result<&'static str> {
+    if client == NethermindChainspec::CLIENT {
+        return Ok(NethermindChainspec::NAME);
+    }
+    let selected = registry.select(&[client.to_string()])?;
+    let format = selected.formats().next().map(|format| format.name);
+    format.with_context(|| format!("no EL genesis format for client {}", client))
+}
+
+fn read_network(format: &str, json: &[u8]) -> Result<NetworkGenesis> {
+    Ok(match format {
+        GethGenesisConfig::NAME => serde_json::from_slice::<GethGenesisConfig>(json)?.into(),
+        BesuGenesisConfig::NAME => serde_json::from_slice::<BesuGenesisConfig>(json)?.into(),
+        NethermindChainspec::NAME => {
+            serde_json::from_slice::<NethermindChainspec>(json)?.try_into()?
+        }
+        name => bail!("{} genesis cannot be converted", name),
+    })
+}
+
+fn write_network(format: &str, network: NetworkGenesis) -> Result<Vec<u8>> {
+    Ok(match format {
+        GethGenesisConfig::NAME => serde_json::to_vec(&GethGenesisConfig::from(network))?,
+        BesuGenesisConfig::NAME => serde_json::to_vec(&BesuGenesisConfig::from(network))?,
+        NethermindChainspec::NAME => serde_json::to_vec(&NethermindChainspec::try_from(network)?)?,
+        name => bail!("{} genesis cannot be converted", name),
+    })
+}
+
+/// Collects the fields of `source` missing from `converted`, such as fork times or account keys
+/// the target format does not carry. Alloc addresses are compared as addresses, and storage
+/// maps, which every format keeps as a whole, are not descended into.
+fn dropped_fields(source: &Value, converted: &Value, path: &str, dropped: &mut Vec<String>) {
+    let (Value::Object(source), Value::Object(converted)) = (source, converted) else {
+        return;
+    };
+    for (key, value) in source {
+        if value.is_null() {
+            continue;
+        }
+        let field = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        let address = key.parse::<Address>().ok();
+        let converted_value = converted.get(key).or_else(|| {
+            converted
+                .iter()
+                .find(|(converted_key, _)| {
+                    address.is_some() && converted_key.parse::<Address>().ok() == address
+                })
+                .map(|(_, converted_value)| converted_value)
+        });
+        match converted_value {
+            None => dropped.push(field),
+            Some(_) if key == "storage" => {}
+            Some(converted_value) => dropped_fields(value, converted_value, &field, dropped),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::fixtures::geth_genesis;
+    use serde_json::json;
+
+    #[test]
+    fn test_converts_geth_genesis_and_reports_dropped_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("genesis.json");
+        let genesis = geth_genesis(
+            0,
+            json!({"osakaTime": 100}),
+            json!({
+                "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266": {
+                    "balance": "0x10",
+                    "storage": {"0x01": "0x02"},
+                    "secretKey": "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                }
+            }),
+        );
+        fs::write(&input, genesis.to_string()).unwrap();
+
+        let conversion = convert(&input, "geth", "besu").unwrap();
+
+        let besu: BesuGenesisConfig = serde_json::from_slice(&conversion.output).unwrap();
+        assert_eq!(besu.config.chain_id, 1337);
+        assert_eq!(besu.gas_limit, "0x17D7840");
+        assert_eq!(besu.alloc.len(), 1);
+        assert_eq!(
+            conversion.dropped,
+            [
+                "alloc.0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266.secretKey",
+                "config.osakaTime"
+            ]
+        );
+        assert!(convert(&input, "geth", "parity").is_err());
+    }
+
+    #[test]
+    fn test_converts_between_geth_and_nethermind() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("genesis.json");
+        let genesis = geth_genesis(
+            0x6712c8a0,
+            json!({"shanghaiTime": 0x6712c8a0u64, "cancunTime": 0x6712c8a0u64}),
+            json!({
+                "0x8943545177806ED17B9F23F0a21ee5948eCaa776": {"balance": "0x10", "nonce": 1}
+            }),
+        );
+        fs::write(&input, genesis.to_string()).unwrap();
+
+        let conversion = convert(&input, "reth", "nethermind").unwrap();
+        assert!(conversion.dropped.is_empty(), "{:?}", conversion.dropped);
+        let chainspec: Value = serde_json::from_slice(&conversion.output).unwrap();
+        assert_eq!(chainspec["params"]["chainID"], "0x539");
+        assert_eq!(
+            chainspec["params"]["eip4844TransitionTimestamp"],
+            "0x6712c8a0"
+        );
+        assert_eq!(chainspec["genesis"]["timestamp"], "0x6712c8a0");
+        assert_eq!(
+            chainspec["accounts"]["0x8943545177806ed17b9f23f0a21ee5948ecaa776"],
+            json!({"balance": "0x10", "nonce": "0x1"})
+        );
+
+        let chainspec = dir.path().join("chainspec.json");
+        fs::write(&chainspec, &conversion.output).unwrap();
+        let conversion = convert(&chainspec, "nethermind", "geth").unwrap();
+        assert!(conversion.dropped.is_empty(), "{:?}", conversion.dropped);
+        let geth: GethGenesisConfig = serde_json::from_slice(&conversion.output).unwrap();
+        assert_eq!(geth.config.chain_id, 1337);
+        assert_eq!(geth.config.cancun_time, 0x6712c8a0);
+        assert_eq!(geth.alloc.values().next().unwrap().nonce, Some(1));
+    }
+
+    #[test]
+    fn test_reports_chainspec_fields_geth_cannot_express() {
+        let input =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/nethermind/chainspec.json");
+
+        let conversion = convert(&input, "nethermind", "geth").unwrap();
+
+        assert_eq!(
+            conversion.dropped,
+            [
+                "accounts.0x0000000000000000000000000000000000000001.builtin",
+                "nodes"
+            ]
+        );
+    }
+}
@@ -1,53 +1,405 @@
-use anyhow::{Result, Context};
+use anyhow::{anyhow, bail, Context, Result};
 use std::{collections::HashMap, env, fs, path::Path};
 
-fn evaluate_expression(expression: &str) -> String {
-    // Check if the expression matches the pattern `${VAR:-default}`
-    if let Some((var_name, default_value)) = parse_expression(expression) {
-        env::var(var_name).unwrap_or_else(|_| default_value.to_string())
-    } else {
-        expression.to_string()
+/// Variables visible to an expansion: keys defined earlier in the file, variables assigned
+/// through `${VAR:=default}`, and finally the process environment.
+struct Scope<'a> {
+    context: &'a HashMap<String, String>,
+    assigned: &'a mut HashMap<String, String>,
+}
+
+impl Scope<'_> {
+    fn lookup(&self, name: &str) -> Option<String> {
+        self.context
+            .get(name)
+            .or_else(|| self.assigned.get(name))
+            .cloned()
+            .or_else(|| env::var(name).ok())
     }
 }
 
-fn parse_expression(expression: &str) -> Option<(&str, &str)> {
-    let mut expression = expression;
-    if expression.contains("#") {
-        expression = expression.split("#").next().unwrap_or("");
-        expression = &expression[0..expression.len() - 2];
-        println!("expression: {}", expression);
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Expands `$VAR` and `${VAR<op>word}` references anywhere in `expression`, where `<op>` is
+/// one of `-`, `=`, `?`, `+`, optionally prefixed with `:` to also treat empty values as unset.
+fn evaluate_expression(expression: &str, scope: &mut Scope) -> Result<String> {
+    let mut result = String::new();
+    let mut chars = expression.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some((_, '$'))) => {
+                chars.next();
+                result.push('$');
+            }
+            '$' => match chars.peek() {
+                Some((_, '{')) => {
+                    let start = index + 2;
+                    let end = find_closing_brace(expression, start)
+                        .ok_or_else(|| anyhow!("Unterminated ${{ in {}", expression))?;
+                    result.push_str(&expand_parameter(&expression[start..end], scope)?);
+                    while matches!(chars.peek(), Some((i, _)) if *i <= end) {
+                        chars.next();
+                    }
+                }
+                Some((_, next)) if is_name_char(*next) => {
+                    let mut name = String::new();
+                    while let Some((_, next)) = chars.peek() {
+                        if !is_name_char(*next) {
+                            break;
+                        }
+                        name.push(*next);
+                        chars.next();
+                    }
+                    result.push_str(&scope.lookup(&name).unwrap_or_default());
+                }
+                _ => result.push('$'),
+            },
+            _ => result.push(c),
+        }
     }
-    if expression.starts_with("${") && expression.ends_with('}') {
-        let trimmed = &expression[2..expression.len() - 1];
-        if let Some((var_name, default_value)) = trimmed.split_once(":-") {
-            return Some((var_name, default_value));
-        } else {
-            return Some((trimmed, ""));
+    Ok(result)
+}
+
+/// Returns the index of the `}` closing the parameter starting at `start`, skipping nested `${...}`.
+fn find_closing_brace(expression: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut previous = None;
+    for (index, c) in expression[start..].char_indices() {
+        match c {
+            '{' if previous == Some('$') => depth += 1,
+            '}' if depth == 0 => return Some(start + index),
+            '}' => depth -= 1,
+            _ => {}
         }
-    } else if expression.starts_with("$") {
-        let trimmed = &expression[1..expression.len()];
-        return Some((trimmed, ""));
+        previous = Some(c);
     }
     None
 }
 
-fn parse_context(content: String) -> Result<HashMap<String, String>> {
-    let mut context = HashMap::new();
+fn expand_parameter(parameter: &str, scope: &mut Scope) -> Result<String> {
+    let name_len = parameter
+        .find(|c: char| !is_name_char(c))
+        .unwrap_or(parameter.len());
+    let (name, rest) = parameter.split_at(name_len);
+    if name.is_empty() {
+        bail!("Invalid parameter expansion ${{{}}}", parameter);
+    }
+    let (check_empty, rest) = match rest.strip_prefix(':') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let value = scope.lookup(name);
+    let is_set = match &value {
+        Some(value) => !(check_empty && value.is_empty()),
+        None => false,
+    };
+
+    let mut operator = rest.chars();
+    let word = operator.as_str().get(1..).unwrap_or("");
+    match operator.next() {
+        None if !check_empty => Ok(value.unwrap_or_default()),
+        Some('-') if is_set => Ok(value.unwrap_or_default()),
+        Some('-') => evaluate_expression(word, scope),
+        Some('=') if is_set => Ok(value.unwrap_or_default()),
+        Some('=') => {
+            let default = evaluate_expression(word, scope)?;
+            scope.assigned.insert(name.to_string(), default.clone());
+            Ok(default)
+        }
+        Some('?') if is_set => Ok(value.unwrap_or_default()),
+        Some('?') => {
+            let message = evaluate_expression(word, scope)?;
+            if message.is_empty() {
+                bail!("{}: parameter null or not set", name);
+            }
+            bail!("{}: {}", name, message);
+        }
+        Some('+') if is_set => evaluate_expression(word, scope),
+        Some('+') => Ok(String::new()),
+        _ => bail!("Unsupported parameter expansion ${{{}}}", parameter),
+    }
+}
+
+/// A piece of a value: `Expand` text still has to go through parameter expansion, while
+/// `Literal` text comes from single quotes or backslash escapes and is kept verbatim.
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Expand(String),
+}
+
+#[derive(Debug, PartialEq)]
+struct Entry {
+    line: usize,
+    key: String,
+    segments: Vec<Segment>,
+}
+
+/// Splits a dotenv file into entries following shell quoting rules: values may span lines
+/// inside quotes, single quotes are literal, and `#` only starts a comment outside quotes.
+struct Tokenizer {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
 
-    for line in content.lines() {
-        if line.starts_with("#") || line.trim().is_empty() {
-            continue;
+impl Tokenizer {
+    fn new(content: &str) -> Self {
+        Tokenizer {
+            chars: content.chars().collect(),
+            position: 0,
+            line: 1,
         }
+    }
 
-        if let Some((key, value)) = line.split_once('=') {
-            let key = key.trim().trim_start_matches("export ").to_string();
-            let value = value.trim().trim_matches('"').to_string();
-            let parsed_value = evaluate_expression(value.as_str());
-            context.insert(key, parsed_value);
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
         }
+        Some(c)
     }
 
-    Ok(context)
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\r')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while !matches!(self.peek(), None | Some('\n')) {
+            self.bump();
+        }
+    }
+
+    fn read_name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(|c| is_name_char(*c)) {
+            name.push(c);
+            self.bump();
+        }
+        name
+    }
+
+    fn next_entry(&mut self) -> Result<Option<Entry>> {
+        loop {
+            match self.peek() {
+                None => return Ok(None),
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('#') => self.skip_comment(),
+                Some(_) => break,
+            }
+        }
+
+        let line = self.line;
+        let mut key = self.read_name();
+        if key == "export" && matches!(self.peek(), Some(' ' | '\t')) {
+            self.skip_blank();
+            key = self.read_name();
+        }
+        if key.is_empty() {
+            bail!("line {}: expected a variable name", line);
+        }
+        self.skip_blank();
+        if self.bump() != Some('=') {
+            bail!("line {}: expected '=' after {}", line, key);
+        }
+        self.skip_blank();
+
+        let segments = self.read_value()?;
+
+        self.skip_blank();
+        match self.peek() {
+            None | Some('\n') => {}
+            Some('#') => self.skip_comment(),
+            Some(c) => bail!(
+                "line {}: unexpected '{}' after value of {}",
+                self.line,
+                c,
+                key
+            ),
+        }
+        Ok(Some(Entry {
+            line,
+            key,
+            segments,
+        }))
+    }
+
+    fn read_value(&mut self) -> Result<Vec<Segment>> {
+        let mut value = ValueBuilder::default();
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' | '\n' => break,
+                '#' if value.is_empty() => break,
+                '\'' => {
+                    let start = self.line;
+                    self.bump();
+                    let mut literal = String::new();
+                    loop {
+                        match self.bump() {
+                            Some('\'') => break,
+                            Some(c) => literal.push(c),
+                            None => bail!("line {}: unterminated single-quoted value", start),
+                        }
+                    }
+                    value.literal(&literal);
+                }
+                '"' => {
+                    let start = self.line;
+                    self.bump();
+                    loop {
+                        match self.bump() {
+                            Some('"') => break,
+                            Some('\\') => match self.bump() {
+                                Some('\n') => {}
+                                Some(c @ ('"' | '\\' | '$' | '`')) => value.literal(&c.to_string()),
+                                Some(c) => {
+                                    value.expand('\\');
+                                    value.expand(c);
+                                }
+                                None => bail!("line {}: unterminated double-quoted value", start),
+                            },
+                            Some('$') if self.peek() == Some('{') => {
+                                self.read_parameter(&mut value)?
+                            }
+                            Some(c) => value.expand(c),
+                            None => bail!("line {}: unterminated double-quoted value", start),
+                        }
+                    }
+                }
+                '\\' => {
+                    self.bump();
+                    match self.bump() {
+                        Some('\n') | None => {}
+                        Some(c) => value.literal(&c.to_string()),
+                    }
+                }
+                '$' => {
+                    self.bump();
+                    if self.peek() == Some('{') {
+                        self.read_parameter(&mut value)?;
+                    } else {
+                        value.expand('$');
+                    }
+                }
+                _ => {
+                    self.bump();
+                    value.expand(c);
+                }
+            }
+        }
+        Ok(value.finish())
+    }
+
+    /// Copies a `${...}` parameter verbatim, including nested parameters and quotes, so that
+    /// its default words are interpreted by the expansion rather than by the tokenizer.
+    fn read_parameter(&mut self, value: &mut ValueBuilder) -> Result<()> {
+        let start = self.line;
+        value.expand('$');
+        let mut depth = 0;
+        let mut previous = '$';
+        while let Some(c) = self.bump() {
+            value.expand(c);
+            match c {
+                '{' if previous == '$' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+            previous = c;
+        }
+        bail!("line {}: unterminated ${{", start)
+    }
+}
+
+#[derive(Default)]
+struct ValueBuilder {
+    segments: Vec<Segment>,
+    current: String,
+}
+
+impl ValueBuilder {
+    fn is_empty(&self) -> bool {
+        self.segments.is_empty() && self.current.is_empty()
+    }
+
+    fn expand(&mut self, c: char) {
+        self.current.push(c);
+    }
+
+    fn literal(&mut self, text: &str) {
+        self.flush();
+        match self.segments.last_mut() {
+            Some(Segment::Literal(literal)) => literal.push_str(text),
+            _ => self.segments.push(Segment::Literal(text.to_string())),
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.current.is_empty() {
+            self.segments
+                .push(Segment::Expand(std::mem::take(&mut self.current)));
+        }
+    }
+
+    fn finish(mut self) -> Vec<Segment> {
+        self.flush();
+        self.segments
+    }
+}
+
+fn parse_context(content: String) -> Result<HashMap<String, String>> {
+    let lines = parse_context_with_lines(content)?;
+    Ok(lines
+        .into_iter()
+        .map(|(key, (value, _))| (key, value))
+        .collect())
+}
+
+/// Like `parse_context`, but also returns the line each key was last defined on.
+fn parse_context_with_lines(content: String) -> Result<HashMap<String, (String, usize)>> {
+    let mut context = HashMap::new();
+    let mut lines = HashMap::new();
+    let mut assigned = HashMap::new();
+    let mut tokenizer = Tokenizer::new(&content);
+
+    while let Some(entry) = tokenizer.next_entry()? {
+        let mut value = String::new();
+        for segment in &entry.segments {
+            match segment {
+                Segment::Literal(literal) => value.push_str(literal),
+                Segment::Expand(expression) => {
+                    let mut scope = Scope {
+                        context: &context,
+                        assigned: &mut assigned,
+                    };
+                    let expanded =
+                        evaluate_expression(expression, &mut scope).with_context(|| {
+                            format!("line {}: could not evaluate {}", entry.line, entry.key)
+                        })?;
+                    value.push_str(&expanded);
+                }
+            }
+        }
+        context.insert(entry.key.clone(), value.clone());
+        lines.insert(entry.key, (value, entry.line));
+    }
+
+    Ok(lines)
 }
 
 pub fn read_defaults<P: AsRef<Path>>(path: P) -> Result<HashMap<String, String>> {
@@ -56,6 +408,13 @@ pub fn read_defaults<P: AsRef<Path>>(path: P) -> Result<HashMap<String, String>>
     Ok(context)
 }
 
+pub fn read_defaults_with_lines<P: AsRef<Path>>(
+    path: P,
+) -> Result<HashMap<String, (String, usize)>> {
+    let content = fs::read_to_string(path).context("Cannot read file")?;
+    parse_context_with_lines(content)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,4 +540,109 @@ mod tests {
 
         assert_eq!(context, expected_context);
     }
+
+    #[test]
+    #[serial]
+    fn test_assigns_default_for_later_references() {
+        let content = "export A=\"${UNSET_A:=7}\"\nexport B=\"$UNSET_A\"";
+
+        let context = parse_context(content.to_string()).unwrap();
+
+        assert_eq!(context["A"], "7");
+        assert_eq!(context["B"], "7");
+        assert!(!context.contains_key("UNSET_A"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_dash_without_colon_keeps_empty_value() {
+        env::set_var("EMPTY_VAR", "");
+        let content = "export A=\"${EMPTY_VAR-fallback}\"\nexport B=\"${EMPTY_VAR:-fallback}\"";
+
+        let context = parse_context(content.to_string()).unwrap();
+        env::remove_var("EMPTY_VAR");
+
+        assert_eq!(context["A"], "");
+        assert_eq!(context["B"], "fallback");
+    }
+
+    #[test]
+    #[serial]
+    fn test_required_variable_reports_message() {
+        let content = "export CHAIN_ID=\"${CHAIN_ID:?chain id must be set}\"";
+
+        let error = parse_context(content.to_string()).unwrap_err();
+
+        assert!(format!("{:#}", error).contains("CHAIN_ID: chain id must be set"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_nested_defaults_and_interpolation() {
+        env::set_var("NESTED_B", "b");
+        let content = "export PREFIX=\"net\"\nexport NAME=\"${PREFIX}-${NESTED_A:-${NESTED_B:-c}}-$PREFIX\" # name";
+
+        let context = parse_context(content.to_string()).unwrap();
+        env::remove_var("NESTED_B");
+
+        assert_eq!(context["NAME"], "net-b-net");
+    }
+
+    #[test]
+    #[serial]
+    fn test_keeps_hash_inside_values() {
+        let content = "export A=\"${UNSET_A:-a#b}\" # comment";
+
+        let context = parse_context(content.to_string()).unwrap();
+
+        assert_eq!(context["A"], "a#b");
+    }
+
+    #[test]
+    #[serial]
+    fn test_multi_line_double_quoted_value() {
+        let content = "export EL_PREMINE_ADDRS=\"{\n  \\\"0x01\\\": {\\\"balance\\\": \\\"1ETH\\\"}\n}\"\nexport NEXT=\"x\"";
+
+        let context = parse_context(content.to_string()).unwrap();
+
+        assert_eq!(
+            context["EL_PREMINE_ADDRS"],
+            "{\n  \"0x01\": {\"balance\": \"1ETH\"}\n}"
+        );
+        assert_eq!(context["NEXT"], "x");
+    }
+
+    #[test]
+    #[serial]
+    fn test_single_quotes_are_literal() {
+        env::set_var("CHAIN_ID", "1337");
+        let content = "export A='${CHAIN_ID:-1} # not a comment'\nexport B=\"\\$CHAIN_ID\"";
+
+        let context = parse_context(content.to_string()).unwrap();
+        env::remove_var("CHAIN_ID");
+
+        assert_eq!(context["A"], "${CHAIN_ID:-1} # not a comment");
+        assert_eq!(context["B"], "$CHAIN_ID");
+    }
+
+    #[test]
+    #[serial]
+    fn test_concatenates_quoted_and_unquoted_parts() {
+        let content = "export A=pre\"mid dle\"'$post'";
+
+        let context = parse_context(content.to_string()).unwrap();
+
+        assert_eq!(context["A"], "premid dle$post");
+    }
+
+    #[test]
+    fn test_reports_line_numbers() {
+        let content = "export A=\"1\"\n\nexport B=\"unterminated\nexport C=3";
+        let error = parse_context(content.to_string()).unwrap_err();
+        assert!(error.to_string().starts_with("line 3"), "{}", error);
+
+        let content = "export A=\"1\"\nexport B=\"2\" trailing";
+        let error = parse_context(content.to_string()).unwrap_err();
+        assert!(error.to_string().starts_with("line 2"), "{}", error);
+    }
 }
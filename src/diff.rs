@@ -0,0 +1,377 @@
+use anyhow::Result;
+use ethers::{
+    types::{Address, H256},
+    utils::to_checksum,
+};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use crate::{
+    cl::genesis_state::{GenesisState, Validator},
+    el::{
+        allocation::Allocation,
+        format_registry::FormatRegistry,
+        genesis_hash::{parse_bytes, parse_h256, parse_u256},
+        network_genesis::{GenesisHeader, NetworkGenesis},
+    },
+    verify::read_cl_config,
+};
+
+/// Compares two metadata folders by meaning rather than by text: EL genesis config and header
+/// fields, alloc entries per address and storage slot, `config.yaml` keys and the validators of
+/// `genesis.ssz`. Returns one line per difference.
+pub fn diff_bundles(a: &Path, b: &Path) -> Result<Vec<String>> {
+    let mut changes = Vec::new();
+
+    for format in FormatRegistry::default().formats() {
+        let file = format.file_name;
+        match (a.join(file).exists(), b.join(file).exists()) {
+            (false, false) => {}
+            (true, false) => changes.push(format!("{}: removed", file)),
+            (false, true) => changes.push(format!("{}: added", file)),
+            (true, true) => {
+                let genesis_a = format.read_network(&a.join(file))?;
+                let genesis_b = format.read_network(&b.join(file))?;
+                diff_el_genesis(file, &genesis_a, &genesis_b, &mut changes)?;
+            }
+        }
+    }
+
+    let cl_config_a = read_optional_cl_config(a)?;
+    let cl_config_b = read_optional_cl_config(b)?;
+    match (&cl_config_a, &cl_config_b) {
+        (Some(_), None) => changes.push("config.yaml: removed".to_string()),
+        (None, Some(_)) => changes.push("config.yaml: added".to_string()),
+        (Some(config_a), Some(config_b)) => {
+            diff_maps("config.yaml", config_a, config_b, &mut changes)
+        }
+        (None, None) => {}
+    }
+
+    let preset = |cl_config: &Option<BTreeMap<String, String>>| {
+        cl_config
+            .as_ref()
+            .and_then(|cl_config| cl_config.get("PRESET_BASE").cloned())
+            .unwrap_or_else(|| "mainnet".to_string())
+    };
+    match (
+        a.join("genesis.ssz").exists(),
+        b.join("genesis.ssz").exists(),
+    ) {
+        (true, false) => changes.push("genesis.ssz: removed".to_string()),
+        (false, true) => changes.push("genesis.ssz: added".to_string()),
+        (true, true) => {
+            let state_a = GenesisState::read(a.join("genesis.ssz"), &preset(&cl_config_a))?;
+            let state_b = GenesisState::read(b.join("genesis.ssz"), &preset(&cl_config_b))?;
+            diff_validators(&state_a.validators, &state_b.validators, &mut changes);
+        }
+        (false, false) => {}
+    }
+    Ok(changes)
+}
+
+fn read_optional_cl_config(folder: &Path) -> Result<Option<BTreeMap<String, String>>> {
+    let path = folder.join("config.yaml");
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(read_cl_config(&path)?))
+}
+
+fn diff_el_genesis(
+    file: &str,
+    a: &NetworkGenesis,
+    b: &NetworkGenesis,
+    changes: &mut Vec<String>,
+) -> Result<()> {
+    let mut fields_a = BTreeMap::new();
+    let mut fields_b = BTreeMap::new();
+    flatten("config", &serde_json::to_value(&a.config)?, &mut fields_a);
+    flatten("config", &serde_json::to_value(&b.config)?, &mut fields_b);
+    for (fields, genesis) in [(&mut fields_a, a), (&mut fields_b, b)] {
+        for (key, value) in header_fields(&genesis.header) {
+            fields.insert(key.to_string(), value);
+        }
+    }
+    diff_maps(file, &fields_a, &fields_b, changes);
+
+    let addresses: BTreeSet<_> = a.alloc.keys().chain(b.alloc.keys()).collect();
+    for address in addresses {
+        let prefix = format!("{} alloc {}", file, to_checksum(address, None));
+        match (a.alloc.get(address), b.alloc.get(address)) {
+            (Some(allocation), None) => changes.push(format!(
+                "{}: removed ({})",
+                prefix,
+                describe_allocation(allocation)
+            )),
+            (None, Some(allocation)) => changes.push(format!(
+                "{}: added ({})",
+                prefix,
+                describe_allocation(allocation)
+            )),
+            (Some(allocation_a), Some(allocation_b)) => {
+                diff_allocation(&prefix, allocation_a, allocation_b, changes)
+            }
+            (None, None) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Header fields written canonically, so that `0x3B9ACA00` and `1000000000` compare equal.
+/// Values that do not parse are kept as written.
+fn header_fields(header: &GenesisHeader) -> Vec<(&'static str, String)> {
+    let number =
+        |value: &str| parse_u256(value).map_or_else(|_| value.to_string(), |n| n.to_string());
+    let hash =
+        |value: &str| parse_h256(value).map_or_else(|_| value.to_string(), |h| format!("{:?}", h));
+    let address = |value: &str| {
+        value
+            .parse::<Address>()
+            .map_or_else(|_| value.to_string(), |address| to_checksum(&address, None))
+    };
+    let bytes =
+        |value: &str| parse_bytes(value).map_or_else(|_| value.to_string(), |b| b.to_string());
+    vec![
+        ("coinbase", address(&header.coinbase)),
+        ("baseFeePerGas", number(&header.base_fee_per_gas)),
+        ("difficulty", number(&header.difficulty)),
+        ("extraData", bytes(&header.extra_data)),
+        ("gasLimit", number(&header.gas_limit)),
+        ("nonce", number(&header.nonce)),
+        ("mixhash", hash(&header.mixhash)),
+        ("parentHash", hash(&header.parent_hash)),
+        ("timestamp", number(&header.timestamp)),
+    ]
+}
+
+fn describe_allocation(allocation: &Allocation) -> String {
+    let mut description = format!("balance {}", allocation.balance);
+    if let Some(code) = &allocation.code {
+        description.push_str(&format!(", {} bytes of code", code.len()));
+    }
+    if let Some(storage) = &allocation.storage {
+        description.push_str(&format!(", {} storage slots", storage.len()));
+    }
+    description
+}
+
+fn diff_allocation(prefix: &str, a: &Allocation, b: &Allocation, changes: &mut Vec<String>) {
+    if a.balance != b.balance {
+        changes.push(format!(
+            "{}: balance {} -> {}",
+            prefix, a.balance, b.balance
+        ));
+    }
+    if a.nonce != b.nonce {
+        changes.push(format!(
+            "{}: nonce {} -> {}",
+            prefix,
+            a.nonce.unwrap_or(0),
+            b.nonce.unwrap_or(0)
+        ));
+    }
+    if a.code != b.code {
+        let size = |allocation: &Allocation| allocation.code.as_ref().map_or(0, |code| code.len());
+        changes.push(format!(
+            "{}: code changed ({} -> {} bytes)",
+            prefix,
+            size(a),
+            size(b)
+        ));
+    }
+    let empty = BTreeMap::new();
+    let storage_a = a.storage.as_ref().unwrap_or(&empty);
+    let storage_b = b.storage.as_ref().unwrap_or(&empty);
+    let slots: BTreeSet<_> = storage_a.keys().chain(storage_b.keys()).collect();
+    for slot in slots {
+        let value = |storage: &BTreeMap<H256, H256>| {
+            storage
+                .get(slot)
+                .map_or_else(|| "unset".to_string(), |value| format!("{:?}", value))
+        };
+        if storage_a.get(slot) != storage_b.get(slot) {
+            changes.push(format!(
+                "{}: storage {:?} {} -> {}",
+                prefix,
+                slot,
+                value(storage_a),
+                value(storage_b)
+            ));
+        }
+    }
+}
+
+/// Flattens nested JSON objects into dotted keys with their values as text.
+fn flatten(prefix: &str, value: &Value, fields: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                flatten(&format!("{}.{}", prefix, key), value, fields);
+            }
+        }
+        Value::Null => {}
+        Value::String(value) => {
+            fields.insert(prefix.to_string(), value.clone());
+        }
+        value => {
+            fields.insert(prefix.to_string(), value.to_string());
+        }
+    }
+}
+
+fn diff_maps(
+    prefix: &str,
+    a: &BTreeMap<String, String>,
+    b: &BTreeMap<String, String>,
+    changes: &mut Vec<String>,
+) {
+    let keys: BTreeSet<_> = a.keys().chain(b.keys()).collect();
+    for key in keys {
+        match (a.get(key), b.get(key)) {
+            (Some(value_a), Some(value_b)) if value_a != value_b => {
+                changes.push(format!("{} {}: {} -> {}", prefix, key, value_a, value_b))
+            }
+            (Some(value), None) => changes.push(format!("{} {}: removed ({})", prefix, key, value)),
+            (None, Some(value)) => changes.push(format!("{} {}: added ({})", prefix, key, value)),
+            _ => {}
+        }
+    }
+}
+
+/// Compares validators by public key, so reordering alone is not reported.
+fn diff_validators(a: &[Validator], b: &[Validator], changes: &mut Vec<String>) {
+    if a.len() != b.len() {
+        changes.push(format!(
+            "genesis.ssz validators: {} -> {}",
+            a.len(),
+            b.len()
+        ));
+    }
+    let by_pubkey = |validators: &[Validator]| -> BTreeMap<String, Validator> {
+        validators
+            .iter()
+            .map(|validator| (validator.pubkey.to_string(), validator.clone()))
+            .collect()
+    };
+    let validators_a = by_pubkey(a);
+    let validators_b = by_pubkey(b);
+    let pubkeys: BTreeSet<_> = validators_a.keys().chain(validators_b.keys()).collect();
+    for pubkey in pubkeys {
+        let prefix = format!("genesis.ssz validator {}", pubkey);
+        match (validators_a.get(pubkey), validators_b.get(pubkey)) {
+            (Some(_), None) => changes.push(format!("{}: removed", prefix)),
+            (None, Some(_)) => changes.push(format!("{}: added", prefix)),
+            (Some(validator_a), Some(validator_b)) => {
+                if validator_a.withdrawal_credentials != validator_b.withdrawal_credentials {
+                    changes.push(format!(
+                        "{}: withdrawal credentials {:?} -> {:?}",
+                        prefix,
+                        validator_a.withdrawal_credentials,
+                        validator_b.withdrawal_credentials
+                    ));
+                }
+                if validator_a.effective_balance != validator_b.effective_balance {
+                    changes.push(format!(
+                        "{}: effective balance {} -> {}",
+                        prefix, validator_a.effective_balance, validator_b.effective_balance
+                    ));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::fixtures::geth_genesis;
+    use serde_json::json;
+    use std::fs;
+
+    fn write_genesis(folder: &Path, cancun_time: u64, alloc: Value) {
+        let genesis = geth_genesis(0, json!({"cancunTime": cancun_time}), alloc);
+        fs::write(folder.join("genesis.json"), genesis.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_diffs_fork_times_alloc_and_cl_config() {
+        let a = tempfile::tempdir().unwrap();
+        let b = tempfile::tempdir().unwrap();
+        write_genesis(
+            a.path(),
+            100,
+            json!({
+                "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266": {"balance": "1"},
+                "0x4242424242424242424242424242424242424242": {
+                    "balance": "0", "code": "0x60", "storage": {"0x01": "0x02", "0x03": "0x04"}
+                }
+            }),
+        );
+        // Same alloc written in a different order and notation, with one storage change.
+        write_genesis(
+            b.path(),
+            200,
+            json!({
+                "0x4242424242424242424242424242424242424242": {
+                    "balance": "0x0", "code": "0x60", "storage": {"0x03": "0x04", "0x01": "0x05"}
+                },
+                "0xF39FD6E51AAD88F6F4CE6AB8827279CFFFB92266": {"balance": "0x1"},
+                "0x000000000000000000000000000000000000dEaD": {"balance": "2"}
+            }),
+        );
+        fs::write(
+            a.path().join("config.yaml"),
+            "DENEB_FORK_EPOCH: 1\nSECONDS_PER_SLOT: 12\n",
+        )
+        .unwrap();
+        fs::write(
+            b.path().join("config.yaml"),
+            "DENEB_FORK_EPOCH: 2\nSECONDS_PER_SLOT: 12\nMAX_BLOBS_PER_BLOCK: 6\n",
+        )
+        .unwrap();
+
+        let changes = diff_bundles(a.path(), b.path()).unwrap();
+
+        assert_eq!(
+            changes,
+            [
+                "genesis.json config.cancunTime: 100 -> 200",
+                "genesis.json alloc 0x000000000000000000000000000000000000dEaD: added (balance 2)",
+                "genesis.json alloc 0x4242424242424242424242424242424242424242: storage \
+                 0x0000000000000000000000000000000000000000000000000000000000000001 \
+                 0x0000000000000000000000000000000000000000000000000000000000000002 -> \
+                 0x0000000000000000000000000000000000000000000000000000000000000005",
+                "config.yaml DENEB_FORK_EPOCH: 1 -> 2",
+                "config.yaml MAX_BLOBS_PER_BLOCK: added (6)",
+            ]
+        );
+        assert!(diff_bundles(a.path(), a.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compares_header_fields_by_value() {
+        let a = tempfile::tempdir().unwrap();
+        let b = tempfile::tempdir().unwrap();
+        write_genesis(a.path(), 100, json!({}));
+        let genesis = fs::read_to_string(a.path().join("genesis.json"))
+            .unwrap()
+            .replace("\"0x3B9ACA00\"", "\"1000000000\"")
+            .replace("\"0x1234\"", "\"4660\"")
+            .replace("\"extraData\":\"\"", "\"extraData\":\"0x\"");
+        fs::write(b.path().join("genesis.json"), &genesis).unwrap();
+
+        assert!(diff_bundles(a.path(), b.path()).unwrap().is_empty());
+
+        let genesis = genesis.replace("\"0x17D7840\"", "\"0x1c9c380\"");
+        fs::write(b.path().join("genesis.json"), genesis).unwrap();
+        assert_eq!(
+            diff_bundles(a.path(), b.path()).unwrap(),
+            ["genesis.json gasLimit: 25000000 -> 30000000"]
+        );
+    }
+}
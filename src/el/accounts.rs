@@ -0,0 +1,364 @@
+use anyhow::{bail, Context, Result};
+use coins_bip32::xkeys::{Parent, XPriv};
+use ethers::core::k256::ecdsa::SigningKey;
+use ethers::core::rand::{rngs::StdRng, RngCore, SeedableRng};
+use ethers::signers::{
+    coins_bip39::{English, Mnemonic},
+    LocalWallet, MnemonicBuilder, Signer,
+};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, path::Path};
+
+use super::{
+    allocation::{parse_balance, Alloc},
+    genesis_config::GenesisConfig,
+    serializabe_to_file::SerializableToFile,
+};
+
+/// Derives the wallet at `derivation_path` from a BIP-39 mnemonic.
+pub fn derive_wallet(mnemonic: &str, derivation_path: &str) -> Result<LocalWallet> {
+    MnemonicBuilder::<English>::default()
+        .phrase(mnemonic)
+        .derivation_path(derivation_path)
+        .with_context(|| format!("Invalid derivation path {}", derivation_path))?
+        .build()
+        .with_context(|| format!("Could not derive wallet at {}", derivation_path))
+}
+
+/// Parent path of bulk premine accounts; the account index is appended as the last component.
+pub const PREMINE_DERIVATION_PREFIX: &str = "m/44'/60'/0'/0";
+
+pub struct PremineAccount {
+    pub derivation_path: String,
+    pub wallet: LocalWallet,
+    pub balance: U256,
+}
+
+/// Lazily derives every premine account: the explicit `el_premine` paths followed by
+/// `el_premine_count` accounts from `el_premine_start_index`. Bulk accounts are derived
+/// from a single parent key so the mnemonic seed is only stretched once. A bulk index whose
+/// path is also listed in `el_premine` is skipped, so the explicit balance applies to it.
+/// Generation derives the accounts once with `derive_premine` and shares them.
+pub fn premine_accounts(
+    genesis_config: &GenesisConfig,
+) -> Result<impl Iterator<Item = Result<PremineAccount>> + '_> {
+    let start = genesis_config.el_premine_start_index;
+    let end = start
+        .checked_add(genesis_config.el_premine_count)
+        .filter(|end| *end <= 1 << 31)
+        .context(
+            "EL_PREMINE_START_INDEX + EL_PREMINE_COUNT exceeds the non-hardened index range",
+        )?;
+    let parent = if start < end {
+        Some(premine_parent_key(&genesis_config.mnemonic)?)
+    } else {
+        None
+    };
+
+    let explicit = genesis_config
+        .el_premine
+        .iter()
+        .map(|(derivation_path, balance)| {
+            Ok(PremineAccount {
+                derivation_path: derivation_path.clone(),
+                wallet: derive_wallet(&genesis_config.mnemonic, derivation_path)?,
+                balance: parse_balance(balance)?,
+            })
+        });
+    let explicit_paths: HashSet<&str> = genesis_config
+        .el_premine
+        .keys()
+        .map(|path| path.as_str())
+        .collect();
+    let bulk = (start..end)
+        .map(|index| (index, format!("{}/{}", PREMINE_DERIVATION_PREFIX, index)))
+        .filter(move |(_, path)| !explicit_paths.contains(path.as_str()))
+        .map(move |(index, derivation_path)| {
+            let Some(parent) = &parent else {
+                bail!("premine parent key was not derived");
+            };
+            let child = parent
+                .derive_child(index)
+                .with_context(|| format!("Could not derive premine account {}", index))?;
+            let signing_key: &SigningKey = child.as_ref();
+            Ok(PremineAccount {
+                derivation_path,
+                wallet: LocalWallet::from(signing_key.clone()),
+                balance: genesis_config.el_premine_balance,
+            })
+        });
+    Ok(explicit.chain(bulk))
+}
+
+/// Every premine account of `premine_accounts`, derived up front.
+pub fn derive_premine(genesis_config: &GenesisConfig) -> Result<Vec<PremineAccount>> {
+    premine_accounts(genesis_config)?.collect()
+}
+
+fn premine_parent_key(mnemonic: &str) -> Result<XPriv> {
+    Mnemonic::<English>::new_from_phrase(mnemonic)
+        .context("Invalid mnemonic")?
+        .master_key(None)
+        .context("Could not derive master key")?
+        .derive_path(PREMINE_DERIVATION_PREFIX)
+        .context("Could not derive premine parent key")
+}
+
+pub fn private_key_hex(wallet: &LocalWallet) -> String {
+    format!(
+        "0x{}",
+        ethers::utils::hex::encode(wallet.signer().to_bytes())
+    )
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub address: Address,
+    pub private_key: String,
+    pub derivation_path: Option<String>,
+    pub balance: U256,
+}
+
+/// Every account with a known private key, written to `accounts.json` for test tooling
+/// instead of leaking `secretKey` into the client genesis files.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Accounts(pub Vec<Account>);
+
+impl Accounts {
+    /// The `premine` accounts derived from `genesis_config` and every alloc entry of it with a
+    /// secret key.
+    pub fn from_genesis_config(
+        genesis_config: &GenesisConfig,
+        premine: &[PremineAccount],
+    ) -> Result<Self> {
+        let mut accounts = Vec::new();
+        for premine in premine {
+            accounts.push(Account {
+                address: premine.wallet.address(),
+                private_key: private_key_hex(&premine.wallet),
+                derivation_path: Some(premine.derivation_path.clone()),
+                balance: premine.balance,
+            });
+        }
+        accounts.extend(with_secret_keys(&genesis_config.el_premine_addrs));
+        accounts.extend(with_secret_keys(
+            &genesis_config.additional_preloaded_contracts,
+        ));
+        accounts.sort_by_key(|account| account.address);
+        Ok(Accounts(accounts))
+    }
+
+    /// Writes a Web3 Secret Storage v3 keystore per account into `dir`, named after its address.
+    /// Salts, IVs and keystore ids are drawn from `rng`, see `keystore_rng`.
+    pub fn write_keystores<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        password: &str,
+        rng: &mut StdRng,
+    ) -> Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).context("Failed to create keystore folder")?;
+        for account in &self.0 {
+            let name = format!("{:?}.json", account.address);
+            let path = dir.join(&name);
+            if path.exists() {
+                println!("{} already exists. Skipping generation...", path.display());
+                continue;
+            }
+            let private_key =
+                ethers::utils::hex::decode(account.private_key.trim_start_matches("0x"))
+                    .with_context(|| format!("Invalid private key for {:?}", account.address))?;
+            LocalWallet::encrypt_keystore(dir, rng, private_key, password, Some(&name))
+                .with_context(|| format!("Could not write keystore for {:?}", account.address))?;
+            replace_keystore_id(&path, rng)?;
+        }
+        Ok(())
+    }
+}
+
+/// Randomness for keystores, reproducible when a `seed` is configured.
+pub fn keystore_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// The keystore library always picks a random UUID; replace it with one drawn from `rng`.
+fn replace_keystore_id(path: &Path, rng: &mut StdRng) -> Result<()> {
+    let mut bytes = [0u8; 16];
+    rng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = ethers::utils::hex::encode(bytes);
+    let id = format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    );
+
+    let mut keystore: serde_json::Value = serde_json::from_slice(&fs::read(path)?)
+        .with_context(|| format!("Invalid keystore {}", path.display()))?;
+    keystore["id"] = serde_json::Value::String(id);
+    fs::write(path, serde_json::to_vec(&keystore)?)
+        .with_context(|| format!("Cannot write {}", path.display()))
+}
+
+impl SerializableToFile for Accounts {}
+
+fn with_secret_keys(alloc: &Alloc) -> impl Iterator<Item = Account> + '_ {
+    alloc.iter().filter_map(|(address, allocation)| {
+        let private_key = allocation.secret_key.as_ref()?;
+        Some(Account {
+            address: *address,
+            private_key: private_key.clone(),
+            derivation_path: None,
+            balance: allocation.balance,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::allocation::Allocation;
+
+    const MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn test_collects_premine_and_secret_key_accounts() {
+        let mut genesis_config = GenesisConfig {
+            mnemonic: MNEMONIC.to_string(),
+            ..Default::default()
+        };
+        genesis_config
+            .el_premine
+            .insert("m/44'/60'/0'/0/0".to_string(), "1ETH".to_string());
+        let contract: Address = "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+            .parse()
+            .unwrap();
+        genesis_config.el_premine_addrs.insert(
+            contract,
+            Allocation {
+                secret_key: Some("0x01".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let premine = derive_premine(&genesis_config).unwrap();
+        let Accounts(accounts) = Accounts::from_genesis_config(&genesis_config, &premine).unwrap();
+
+        assert_eq!(accounts.len(), 2);
+        let premine = accounts
+            .iter()
+            .find(|a| a.derivation_path.is_some())
+            .unwrap();
+        assert_eq!(
+            premine.address,
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            premine.private_key,
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+        assert_eq!(premine.balance, U256::exp10(18));
+        assert!(accounts
+            .iter()
+            .any(|a| a.address == contract && a.private_key == "0x01"));
+    }
+
+    #[test]
+    fn test_writes_decryptable_keystores() {
+        let wallet = derive_wallet(MNEMONIC, "m/44'/60'/0'/0/1").unwrap();
+        let accounts = Accounts(vec![Account {
+            address: wallet.address(),
+            private_key: private_key_hex(&wallet),
+            derivation_path: Some("m/44'/60'/0'/0/1".to_string()),
+            balance: U256::zero(),
+        }]);
+        let dir = tempfile::tempdir().unwrap();
+
+        accounts
+            .write_keystores(dir.path(), "secret", &mut keystore_rng(Some(7)))
+            .unwrap();
+
+        let keystore = dir.path().join(format!("{:?}.json", wallet.address()));
+        let decrypted = LocalWallet::decrypt_keystore(&keystore, "secret").unwrap();
+        assert_eq!(decrypted.address(), wallet.address());
+
+        let other_dir = tempfile::tempdir().unwrap();
+        accounts
+            .write_keystores(other_dir.path(), "secret", &mut keystore_rng(Some(7)))
+            .unwrap();
+        let other_keystore = other_dir
+            .path()
+            .join(format!("{:?}.json", wallet.address()));
+        assert_eq!(
+            fs::read(keystore).unwrap(),
+            fs::read(other_keystore).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_derives_bulk_premine_from_start_index() {
+        let genesis_config = GenesisConfig {
+            mnemonic: MNEMONIC.to_string(),
+            el_premine_count: 2,
+            el_premine_start_index: 1,
+            el_premine_balance: U256::from(5),
+            ..Default::default()
+        };
+
+        let premine = premine_accounts(&genesis_config)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(premine.len(), 2);
+        for (account, index) in premine.iter().zip(1..) {
+            let path = format!("m/44'/60'/0'/0/{}", index);
+            assert_eq!(account.derivation_path, path);
+            assert_eq!(
+                account.wallet.address(),
+                derive_wallet(MNEMONIC, &path).unwrap().address()
+            );
+            assert_eq!(account.balance, U256::from(5));
+        }
+    }
+
+    #[test]
+    fn test_explicit_premine_path_replaces_bulk_account() {
+        let mut genesis_config = GenesisConfig {
+            mnemonic: MNEMONIC.to_string(),
+            el_premine_count: 3,
+            el_premine_balance: U256::from(5),
+            ..Default::default()
+        };
+        genesis_config
+            .el_premine
+            .insert("m/44'/60'/0'/0/1".to_string(), "1ETH".to_string());
+
+        let premine = derive_premine(&genesis_config).unwrap();
+
+        let paths: Vec<_> = premine
+            .iter()
+            .map(|account| (account.derivation_path.as_str(), account.balance))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("m/44'/60'/0'/0/1", U256::exp10(18)),
+                ("m/44'/60'/0'/0/0", U256::from(5)),
+                ("m/44'/60'/0'/0/2", U256::from(5)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,486 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::types::{Address, Bytes, H256, U256};
+use ethers::utils::{parse_ether, to_checksum};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// Maximum size of deployed contract code (EIP-170).
+pub const MAX_CODE_SIZE: usize = 24576;
+
+/// Sorted by address so serialized genesis files are byte-reproducible.
+pub type Alloc = BTreeMap<Address, Allocation>;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Allocation {
+    #[serde(deserialize_with = "deserialize_balance")]
+    pub balance: U256,
+    pub code: Option<Bytes>,
+    #[serde(default, deserialize_with = "deserialize_storage")]
+    pub storage: Option<BTreeMap<H256, H256>>,
+    pub nonce: Option<u64>,
+    /// Accepted on input but never written to client genesis files, see `accounts.json`.
+    #[serde(skip_serializing)]
+    pub secret_key: Option<String>,
+}
+
+impl Allocation {
+    pub fn with_balance(balance: U256) -> Self {
+        Allocation {
+            balance,
+            ..Default::default()
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(code) = &self.code {
+            if code.len() > MAX_CODE_SIZE {
+                bail!(
+                    "code is {} bytes, exceeding the EIP-170 limit of {} bytes",
+                    code.len(),
+                    MAX_CODE_SIZE
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a hex address, rejecting mixed-case input that fails the EIP-55 checksum.
+pub fn parse_address(value: &str) -> Result<Address> {
+    let hex = value.strip_prefix("0x").unwrap_or(value);
+    if hex.len() != 40 {
+        bail!(
+            "address {} must be 20 bytes, got {} hex characters",
+            value,
+            hex.len()
+        );
+    }
+    let bytes = ethers::utils::hex::decode(hex)
+        .with_context(|| format!("address {} is not valid hex", value))?;
+    let address = Address::from_slice(&bytes);
+
+    let has_lower = hex.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = hex.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        let checksummed = to_checksum(&address, None);
+        if checksummed[2..] != *hex {
+            bail!(
+                "address {} has an invalid EIP-55 checksum, expected {}",
+                value,
+                checksummed
+            );
+        }
+    }
+    Ok(address)
+}
+
+/// Parses a balance given in wei (decimal or 0x-prefixed hex) or in ether with an `ETH` suffix.
+pub fn parse_balance(value: &str) -> Result<U256> {
+    let value = value.trim();
+    if let Some(ether) = value.strip_suffix("ETH") {
+        return parse_ether(ether.trim())
+            .map_err(|e| anyhow!("invalid ether amount {}: {}", value, e));
+    }
+    if let Some(hex) = value.strip_prefix("0x") {
+        return U256::from_str_radix(hex, 16)
+            .map_err(|e| anyhow!("invalid hex balance {}: {}", value, e));
+    }
+    U256::from_dec_str(value).map_err(|e| anyhow!("invalid balance {}: {}", value, e))
+}
+
+/// Parses a storage slot key or value, left-padding values shorter than 32 bytes.
+pub fn parse_storage_word(value: &str) -> Result<H256> {
+    let hex = value.strip_prefix("0x").unwrap_or(value);
+    let hex = if hex.len() % 2 == 1 {
+        format!("0{}", hex)
+    } else {
+        hex.to_string()
+    };
+    let bytes = ethers::utils::hex::decode(&hex)
+        .with_context(|| format!("storage word {} is not valid hex", value))?;
+    if bytes.len() > 32 {
+        bail!(
+            "storage word {} is {} bytes, must be at most 32",
+            value,
+            bytes.len()
+        );
+    }
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(H256(word))
+}
+
+/// Builds an alloc map from raw entries, naming the offending address on any error.
+pub fn parse_alloc(entries: HashMap<String, serde_json::Value>) -> Result<Alloc> {
+    let mut alloc = Alloc::new();
+    let mut seen: HashMap<Address, String> = HashMap::new();
+    for (key, value) in entries {
+        let address = parse_address(&key)?;
+        let allocation: Allocation = serde_json::from_value(value)
+            .with_context(|| format!("invalid allocation for {}", key))?;
+        allocation
+            .validate()
+            .with_context(|| format!("invalid allocation for {}", key))?;
+        if let Some(previous) = seen.insert(address, key.clone()) {
+            bail!(
+                "duplicate allocation for {:?}: listed as both {} and {}",
+                address,
+                previous,
+                key
+            );
+        }
+        alloc.insert(address, allocation);
+    }
+    Ok(alloc)
+}
+
+pub fn deserialize_alloc<'de, D>(deserializer: D) -> Result<Alloc, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entries = HashMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    parse_alloc(entries).map_err(|e| D::Error::custom(format!("{:#}", e)))
+}
+
+/// How to resolve an alloc entry that targets an address already present in the alloc.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    #[default]
+    Error,
+    LastWins,
+    MergeBalances,
+    MergeStorage,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().replace('_', "-").as_str() {
+            "error" => Ok(ConflictPolicy::Error),
+            "last-wins" => Ok(ConflictPolicy::LastWins),
+            "merge-balances" => Ok(ConflictPolicy::MergeBalances),
+            "merge-storage" => Ok(ConflictPolicy::MergeStorage),
+            _ => bail!(
+                "unknown alloc conflict policy {}, expected one of error, last-wins, merge-balances, merge-storage",
+                value
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ConflictPolicy::Error => "error",
+            ConflictPolicy::LastWins => "last-wins",
+            ConflictPolicy::MergeBalances => "merge-balances",
+            ConflictPolicy::MergeStorage => "merge-storage",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Where an alloc entry came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllocSource {
+    Template,
+    Premine,
+    PremineAddrs,
+    PreloadedContracts,
+}
+
+impl fmt::Display for AllocSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AllocSource::Template => "template",
+            AllocSource::Premine => "premine",
+            AllocSource::PremineAddrs => "EL_PREMINE_ADDRS",
+            AllocSource::PreloadedContracts => "ADDITIONAL_PRELOADED_CONTRACTS",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An address whose existing alloc entry was replaced or merged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AllocOverride {
+    pub address: Address,
+    pub previous: AllocSource,
+    pub source: AllocSource,
+    pub policy: ConflictPolicy,
+}
+
+impl fmt::Display for AllocOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} from {} overrides {} entry ({})",
+            self.address, self.source, self.previous, self.policy
+        )
+    }
+}
+
+/// Inserts entries into an alloc, applying a conflict policy and recording every override.
+/// Entries already in the alloc when the merger is created are attributed to the template.
+#[derive(Debug, Default)]
+pub struct AllocMerger {
+    policy: ConflictPolicy,
+    sources: HashMap<Address, AllocSource>,
+    overrides: Vec<AllocOverride>,
+}
+
+impl AllocMerger {
+    pub fn new(policy: ConflictPolicy) -> Self {
+        AllocMerger {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        alloc: &mut Alloc,
+        address: Address,
+        account: Allocation,
+        source: AllocSource,
+    ) -> Result<()> {
+        let previous_source = self.sources.insert(address, source);
+        let Some(existing) = alloc.get_mut(&address) else {
+            alloc.insert(address, account);
+            return Ok(());
+        };
+        let previous = previous_source.unwrap_or(AllocSource::Template);
+
+        match self.policy {
+            ConflictPolicy::Error => bail!(
+                "alloc conflict at {:?}: {} entry would override {} entry",
+                address,
+                source,
+                previous
+            ),
+            ConflictPolicy::LastWins => *existing = account,
+            ConflictPolicy::MergeBalances => {
+                existing.balance = existing
+                    .balance
+                    .checked_add(account.balance)
+                    .with_context(|| format!("merged balance overflows at {:?}", address))?;
+                existing.code = existing.code.take().or(account.code);
+                existing.storage = existing.storage.take().or(account.storage);
+                existing.nonce = existing.nonce.or(account.nonce);
+                existing.secret_key = existing.secret_key.take().or(account.secret_key);
+            }
+            ConflictPolicy::MergeStorage => {
+                let mut storage = existing.storage.take().unwrap_or_default();
+                storage.extend(account.storage.unwrap_or_default());
+                existing.balance = account.balance;
+                existing.code = account.code.or(existing.code.take());
+                existing.storage = Some(storage);
+                existing.nonce = account.nonce.or(existing.nonce);
+                existing.secret_key = account.secret_key.or(existing.secret_key.take());
+            }
+        }
+        self.overrides.push(AllocOverride {
+            address,
+            previous,
+            source,
+            policy: self.policy,
+        });
+        Ok(())
+    }
+
+    pub fn into_overrides(self) -> Vec<AllocOverride> {
+        self.overrides
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawBalance {
+    Text(String),
+    Number(u64),
+}
+
+fn deserialize_balance<'de, D>(deserializer: D) -> Result<U256, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match RawBalance::deserialize(deserializer)? {
+        RawBalance::Text(text) => {
+            parse_balance(&text).map_err(|e| D::Error::custom(format!("{:#}", e)))
+        }
+        RawBalance::Number(number) => Ok(U256::from(number)),
+    }
+}
+
+fn deserialize_storage<'de, D>(deserializer: D) -> Result<Option<BTreeMap<H256, H256>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = Option::<HashMap<String, String>>::deserialize(deserializer)?;
+    raw.map(|entries| {
+        entries
+            .iter()
+            .map(|(key, value)| {
+                let key = parse_storage_word(key)?;
+                let value = parse_storage_word(value)?;
+                Ok((key, value))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()
+    })
+    .transpose()
+    .map_err(|e| D::Error::custom(format!("{:#}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(str: &str) -> Result<Alloc> {
+        parse_alloc(serde_json::from_str(str)?)
+    }
+
+    #[test]
+    fn test_parses_checksummed_and_lowercase_addresses() {
+        let checksummed = parse_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap();
+        let lowercase = parse_address("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        assert_eq!(checksummed, lowercase);
+    }
+
+    #[test]
+    fn test_rejects_bad_checksum_and_length() {
+        assert!(parse_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").is_err());
+        assert!(parse_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea").is_err());
+    }
+
+    #[test]
+    fn test_parses_balance_formats() {
+        assert_eq!(parse_balance("1ETH").unwrap(), U256::exp10(18));
+        assert_eq!(parse_balance("0x10").unwrap(), U256::from(16));
+        assert_eq!(parse_balance("1000").unwrap(), U256::from(1000));
+        assert!(parse_balance("ten").is_err());
+    }
+
+    #[test]
+    fn test_mixed_case_duplicates_collide() {
+        let result = parse(
+            "{\"0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed\": {\"balance\": \"1\"},
+              \"0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed\": {\"balance\": \"2\"}}",
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("duplicate allocation"));
+    }
+
+    #[test]
+    fn test_pads_short_storage_and_rejects_oversized() {
+        let alloc = parse(
+            "{\"0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed\": {\"balance\": \"0\", \"storage\": {\"0x01\": \"0x2\"}}}",
+        )
+        .unwrap();
+        let storage = alloc.values().next().unwrap().storage.clone().unwrap();
+        assert_eq!(storage[&H256::from_low_u64_be(1)], H256::from_low_u64_be(2));
+
+        let oversized = format!("0x{}", "ab".repeat(33));
+        let result = parse(&format!(
+            "{{\"0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed\": {{\"balance\": \"0\", \"storage\": {{\"0x01\": \"{}\"}}}}}}",
+            oversized
+        ));
+        let message = format!("{:#}", result.unwrap_err());
+        assert!(message.contains("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"));
+    }
+
+    #[test]
+    fn test_rejects_code_over_eip170_limit() {
+        let code = format!("0x{}", "00".repeat(MAX_CODE_SIZE + 1));
+        let result = parse(&format!(
+            "{{\"0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed\": {{\"balance\": \"0\", \"code\": \"{}\"}}}}",
+            code
+        ));
+        assert!(format!("{:#}", result.unwrap_err()).contains("EIP-170"));
+    }
+
+    fn merge(policy: ConflictPolicy) -> Result<(Alloc, Vec<AllocOverride>)> {
+        let address = parse_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        let mut alloc = Alloc::new();
+        alloc.insert(
+            address,
+            Allocation {
+                balance: U256::from(1),
+                code: Some(Bytes::from(vec![0x60])),
+                storage: Some(BTreeMap::from([(
+                    H256::from_low_u64_be(1),
+                    H256::from_low_u64_be(1),
+                )])),
+                ..Default::default()
+            },
+        );
+        let incoming = Allocation {
+            balance: U256::from(2),
+            storage: Some(BTreeMap::from([(
+                H256::from_low_u64_be(2),
+                H256::from_low_u64_be(2),
+            )])),
+            ..Default::default()
+        };
+
+        let mut merger = AllocMerger::new(policy);
+        merger.insert(&mut alloc, address, incoming, AllocSource::Premine)?;
+        Ok((alloc, merger.into_overrides()))
+    }
+
+    #[test]
+    fn test_conflict_policy_error_names_sources() {
+        let message = merge(ConflictPolicy::Error).unwrap_err().to_string();
+        assert!(message.contains("premine entry would override template entry"));
+    }
+
+    #[test]
+    fn test_conflict_policy_last_wins_reports_override() {
+        let (alloc, overrides) = merge(ConflictPolicy::LastWins).unwrap();
+        let entry = alloc.values().next().unwrap();
+        assert_eq!(entry.balance, U256::from(2));
+        assert_eq!(entry.code, None);
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].previous, AllocSource::Template);
+        assert_eq!(overrides[0].source, AllocSource::Premine);
+    }
+
+    #[test]
+    fn test_conflict_policy_merges() {
+        let (alloc, _) = merge(ConflictPolicy::MergeBalances).unwrap();
+        let entry = alloc.values().next().unwrap();
+        assert_eq!(entry.balance, U256::from(3));
+        assert!(entry.code.is_some());
+        assert_eq!(entry.storage.as_ref().unwrap().len(), 1);
+
+        let (alloc, _) = merge(ConflictPolicy::MergeStorage).unwrap();
+        let entry = alloc.values().next().unwrap();
+        assert_eq!(entry.balance, U256::from(2));
+        assert!(entry.code.is_some());
+        assert_eq!(entry.storage.as_ref().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parses_conflict_policy() {
+        assert_eq!(
+            "merge_balances".parse::<ConflictPolicy>().unwrap(),
+            ConflictPolicy::MergeBalances
+        );
+        assert!("overwrite".parse::<ConflictPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_secret_key_is_not_serialized() {
+        let allocation = Allocation {
+            secret_key: Some("0x01".to_string()),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&allocation).unwrap();
+        assert!(!json.contains("secretKey"));
+    }
+}
@@ -22,7 +22,7 @@ pub struct Config {
     #[serde(rename = "pragueEOFTime")]
     pub prague_time: Option<u64>,
     pub prague_eoftime: Option<u64>,
-    pub ethash: std::collections::HashMap<String, String>,
+    pub ethash: std::collections::BTreeMap<String, String>,
 }
 
 impl Config {
@@ -46,19 +46,7 @@ impl Config {
             deposit_contract_address,
             prague_time: None,
             prague_eoftime: None,
-            ethash: std::collections::HashMap::new(),
+            ethash: std::collections::BTreeMap::new(),
         }
     }
 }
- 
-
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Allocation {
-    pub balance: String,
-    pub code: Option<String>,
-    pub storage: Option<std::collections::HashMap<String, String>>,
-    pub nonce: Option<u64>,
-    pub secret_key: Option<String>,
-}
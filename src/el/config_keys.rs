@@ -0,0 +1,224 @@
+use anyhow::{bail, Result};
+use std::{collections::HashMap, env, fmt, str::FromStr};
+
+/// Every key `GenesisConfigBuilder::from_hashmap` understands.
+pub const KNOWN_KEYS: &[&str] = &[
+    "CONFIG_MODE",
+    "PRESET_BASE",
+    "CHAIN_ID",
+    "DEPOSIT_CONTRACT_ADDRESS",
+    "EL_AND_CL_MNEMONIC",
+    "EL_PREMINE_PATHS",
+    "EL_PREMINE_COUNT",
+    "EL_PREMINE_BALANCE",
+    "EL_PREMINE_START_INDEX",
+    "EL_PREMINE_KEYSTORES",
+    "EL_PREMINE_KEYSTORE_PASSWORD",
+    "EL_PREMINE_ADDRS",
+    "ADDITIONAL_PRELOADED_CONTRACTS",
+    "ALLOC_CONFLICT_POLICY",
+    "GENESIS_TIMESTAMP",
+    "GENESIS_DELAY",
+    "GENESIS_GASLIMIT",
+    "SLOT_DURATION_IN_SECONDS",
+    "CAPELLA_FORK_EPOCH",
+    "DENEB_FORK_EPOCH",
+    "ELECTRA_FORK_EPOCH",
+    "EOF_ACTIVATION_EPOCH",
+    "EL_CLIENTS",
+    "CL_CLIENTS",
+    "SEED",
+    // Consensus layer keys of the upstream values.env, see `ConsensusParams`.
+    "NUMBER_OF_VALIDATORS",
+    "WITHDRAWAL_TYPE",
+    "WITHDRAWAL_ADDRESS",
+    "CL_EXEC_BLOCK",
+    "DEPOSIT_CONTRACT_BLOCK",
+    "GENESIS_FORK_VERSION",
+    "ALTAIR_FORK_VERSION",
+    "BELLATRIX_FORK_VERSION",
+    "CAPELLA_FORK_VERSION",
+    "DENEB_FORK_VERSION",
+    "ELECTRA_FORK_VERSION",
+    "EIP7594_FORK_VERSION",
+    "EIP7594_FORK_EPOCH",
+    "MAX_PER_EPOCH_ACTIVATION_CHURN_LIMIT",
+    "CHURN_LIMIT_QUOTIENT",
+    "EJECTION_BALANCE",
+    "ETH1_FOLLOW_DISTANCE",
+    "MIN_VALIDATOR_WITHDRAWABILITY_DELAY",
+    "SHARD_COMMITTEE_PERIOD",
+    "MIN_EPOCHS_FOR_BLOCK_REQUESTS",
+    "MAX_BLOBS_PER_BLOCK",
+    "SAMPLES_PER_SLOT",
+    "CUSTODY_REQUIREMENT",
+    "DATA_COLUMN_SIDECAR_SUBNET_COUNT",
+    "TARGET_NUMBER_OF_PEERS",
+    "BEACON_STATIC_ENR",
+    "SHADOW_FORK_FILE",
+    "SHADOW_FORK_RPC",
+];
+
+/// Keys `resolve` writes for values computed from the config. They are accepted, so a
+/// resolved file can be replayed, but ignored when read back.
+pub const COMPUTED_KEYS: &[&str] = &[
+    "GENESIS_TIME",
+    "SHANGHAI_TIME",
+    "CANCUN_TIME",
+    "PRAGUE_TIME",
+    "PRAGUE_EOF_TIME",
+    "EL_PREMINE_DERIVED_ADDRS",
+];
+
+/// Upstream keys that only affect the CL genesis state, which this crate does not generate.
+/// They are known, so upstream files pass the unknown key check, but setting them is reported.
+pub const UNSUPPORTED_KEYS: &[&str] = &[
+    "WITHDRAWAL_TYPE",
+    "WITHDRAWAL_ADDRESS",
+    "CL_EXEC_BLOCK",
+    "DEPOSIT_CONTRACT_BLOCK",
+    "BEACON_STATIC_ENR",
+    "SHADOW_FORK_FILE",
+    "SHADOW_FORK_RPC",
+];
+
+/// How to treat config keys that are unknown or whose values cannot be parsed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConfigMode {
+    /// Reject the config, for CI where a typo must not silently do nothing.
+    Strict,
+    /// Print a warning and ignore the key, for upstream env files carrying unrelated keys.
+    #[default]
+    Warn,
+}
+
+impl ConfigMode {
+    /// Strict when running under CI (the `CI` environment variable is set), otherwise warn.
+    pub fn from_environment() -> Self {
+        match env::var("CI") {
+            Ok(ci) if !ci.is_empty() && ci != "false" && ci != "0" => ConfigMode::Strict,
+            _ => ConfigMode::Warn,
+        }
+    }
+
+    /// Fails in strict mode and prints `message` as a warning otherwise.
+    pub fn report(&self, message: String) -> Result<()> {
+        match self {
+            ConfigMode::Strict => bail!(message),
+            ConfigMode::Warn => {
+                eprintln!("warning: {}", message);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for ConfigMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "strict" => Ok(ConfigMode::Strict),
+            "warn" => Ok(ConfigMode::Warn),
+            _ => bail!("unknown config mode {}, expected strict or warn", value),
+        }
+    }
+}
+
+impl fmt::Display for ConfigMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigMode::Strict => write!(f, "strict"),
+            ConfigMode::Warn => write!(f, "warn"),
+        }
+    }
+}
+
+/// Returns the closest known key to `key`, if any is similar enough to be a likely typo.
+pub fn suggest_key(key: &str) -> Option<&'static str> {
+    KNOWN_KEYS
+        .iter()
+        .map(|known| (*known, strsim::levenshtein(key, known)))
+        .filter(|(_, distance)| *distance <= 3)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(known, _)| known)
+}
+
+/// Reports every key of `config_map` that is not in `KNOWN_KEYS`, failing in strict mode.
+pub fn check_unknown_keys(config_map: &HashMap<String, String>, mode: ConfigMode) -> Result<()> {
+    let mut unknown: Vec<&String> = config_map
+        .keys()
+        .filter(|key| !KNOWN_KEYS.contains(&key.as_str()) && !COMPUTED_KEYS.contains(&key.as_str()))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    unknown.sort();
+
+    let descriptions: Vec<String> = unknown
+        .iter()
+        .map(|key| match suggest_key(key) {
+            Some(suggestion) => format!("{} (did you mean {}?)", key, suggestion),
+            None => key.to_string(),
+        })
+        .collect();
+    mode.report(format!("unknown config keys: {}", descriptions.join(", ")))
+}
+
+/// Reports every key of `UNSUPPORTED_KEYS` set in `config_map`, failing in strict mode.
+pub fn check_unsupported_keys(
+    config_map: &HashMap<String, String>,
+    mode: ConfigMode,
+) -> Result<()> {
+    let set: Vec<&str> = UNSUPPORTED_KEYS
+        .iter()
+        .copied()
+        .filter(|key| config_map.get(*key).is_some_and(|value| !value.is_empty()))
+        .collect();
+    if set.is_empty() {
+        return Ok(());
+    }
+    mode.report(format!(
+        "unsupported config keys: {} (the CL genesis state is not generated)",
+        set.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggests_close_keys() {
+        assert_eq!(suggest_key("ELECTRA_FORK_EPOC"), Some("ELECTRA_FORK_EPOCH"));
+        assert_eq!(suggest_key("NOT_A_SETTING_AT_ALL"), None);
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_unknown_keys() {
+        let mut config_map = HashMap::new();
+        config_map.insert("CHAIN_ID".to_string(), "1337".to_string());
+        config_map.insert("ELECTRA_FORK_EPOC".to_string(), "1".to_string());
+
+        let error = check_unknown_keys(&config_map, ConfigMode::Strict).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown config keys: ELECTRA_FORK_EPOC (did you mean ELECTRA_FORK_EPOCH?)"
+        );
+        assert!(check_unknown_keys(&config_map, ConfigMode::Warn).is_ok());
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_unsupported_keys() {
+        let mut config_map = HashMap::new();
+        config_map.insert("WITHDRAWAL_TYPE".to_string(), "0x01".to_string());
+        config_map.insert("SHADOW_FORK_RPC".to_string(), "".to_string());
+
+        let error = check_unsupported_keys(&config_map, ConfigMode::Strict).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unsupported config keys: WITHDRAWAL_TYPE (the CL genesis state is not generated)"
+        );
+        assert!(check_unsupported_keys(&config_map, ConfigMode::Warn).is_ok());
+    }
+}
@@ -0,0 +1,242 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+use super::config_keys::ConfigMode;
+use super::genesis_config::parse_value;
+
+/// Epoch the upstream generator uses for forks that are not scheduled.
+pub const FAR_FUTURE_EPOCH: u64 = u64::MAX;
+
+/// CL clients `CL_CLIENTS` can select.
+pub const CL_CLIENTS: &[&str] = &["lighthouse", "teku", "prysm", "nimbus", "lodestar", "grandine"];
+
+/// A 4 byte consensus fork version such as `0x10000038`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ForkVersion(pub [u8; 4]);
+
+impl FromStr for ForkVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let digits = value.trim().trim_start_matches("0x");
+        if digits.len() != 8 {
+            bail!("fork version {} must be 4 bytes of hex", value);
+        }
+        let number = u32::from_str_radix(digits, 16)
+            .with_context(|| format!("fork version {} is not hex", value))?;
+        Ok(ForkVersion(number.to_be_bytes()))
+    }
+}
+
+impl fmt::Display for ForkVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:08x}", u32::from_be_bytes(self.0))
+    }
+}
+
+/// Consensus layer settings of the upstream `values.env`, with the upstream defaults.
+/// They do not change the EL genesis files and fill in the CL `config.yaml`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsensusParams {
+    pub number_of_validators: u64,
+    pub genesis_fork_version: ForkVersion,
+    pub altair_fork_version: ForkVersion,
+    pub bellatrix_fork_version: ForkVersion,
+    pub capella_fork_version: ForkVersion,
+    pub deneb_fork_version: ForkVersion,
+    pub electra_fork_version: ForkVersion,
+    pub eip7594_fork_version: ForkVersion,
+    pub eip7594_fork_epoch: u64,
+    pub max_per_epoch_activation_churn_limit: u64,
+    pub churn_limit_quotient: u64,
+    pub ejection_balance: u64,
+    pub eth1_follow_distance: u64,
+    pub min_validator_withdrawability_delay: u64,
+    pub shard_committee_period: u64,
+    pub min_epochs_for_block_requests: u64,
+    pub max_blobs_per_block: u64,
+    pub samples_per_slot: u64,
+    pub custody_requirement: u64,
+    pub data_column_sidecar_subnet_count: u64,
+    pub target_number_of_peers: u64,
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        ConsensusParams {
+            number_of_validators: 64,
+            genesis_fork_version: ForkVersion([0x10, 0, 0, 0x38]),
+            altair_fork_version: ForkVersion([0x20, 0, 0, 0x38]),
+            bellatrix_fork_version: ForkVersion([0x30, 0, 0, 0x38]),
+            capella_fork_version: ForkVersion([0x40, 0, 0, 0x38]),
+            deneb_fork_version: ForkVersion([0x50, 0, 0, 0x38]),
+            electra_fork_version: ForkVersion([0x60, 0, 0, 0x38]),
+            eip7594_fork_version: ForkVersion([0x70, 0, 0, 0x38]),
+            eip7594_fork_epoch: FAR_FUTURE_EPOCH,
+            max_per_epoch_activation_churn_limit: 8,
+            churn_limit_quotient: 65536,
+            ejection_balance: 16_000_000_000,
+            eth1_follow_distance: 2048,
+            min_validator_withdrawability_delay: 256,
+            shard_committee_period: 256,
+            min_epochs_for_block_requests: 33024,
+            max_blobs_per_block: 6,
+            samples_per_slot: 8,
+            custody_requirement: 4,
+            data_column_sidecar_subnet_count: 128,
+            target_number_of_peers: 70,
+        }
+    }
+}
+
+impl ConsensusParams {
+    /// Overrides the settings configured in `config_map`, reporting malformed values per `mode`.
+    pub fn apply(&mut self, config_map: &HashMap<String, String>, mode: ConfigMode) -> Result<()> {
+        if let Some(number_of_validators) = parse_value(config_map, "NUMBER_OF_VALIDATORS", mode)? {
+            self.number_of_validators = number_of_validators;
+        }
+        if let Some(genesis_fork_version) = parse_value(config_map, "GENESIS_FORK_VERSION", mode)? {
+            self.genesis_fork_version = genesis_fork_version;
+        }
+        if let Some(altair_fork_version) = parse_value(config_map, "ALTAIR_FORK_VERSION", mode)? {
+            self.altair_fork_version = altair_fork_version;
+        }
+        if let Some(bellatrix_fork_version) =
+            parse_value(config_map, "BELLATRIX_FORK_VERSION", mode)?
+        {
+            self.bellatrix_fork_version = bellatrix_fork_version;
+        }
+        if let Some(capella_fork_version) = parse_value(config_map, "CAPELLA_FORK_VERSION", mode)? {
+            self.capella_fork_version = capella_fork_version;
+        }
+        if let Some(deneb_fork_version) = parse_value(config_map, "DENEB_FORK_VERSION", mode)? {
+            self.deneb_fork_version = deneb_fork_version;
+        }
+        if let Some(electra_fork_version) = parse_value(config_map, "ELECTRA_FORK_VERSION", mode)? {
+            self.electra_fork_version = electra_fork_version;
+        }
+        if let Some(eip7594_fork_version) = parse_value(config_map, "EIP7594_FORK_VERSION", mode)? {
+            self.eip7594_fork_version = eip7594_fork_version;
+        }
+        if let Some(eip7594_fork_epoch) = parse_value(config_map, "EIP7594_FORK_EPOCH", mode)? {
+            self.eip7594_fork_epoch = eip7594_fork_epoch;
+        }
+        if let Some(max_per_epoch_activation_churn_limit) =
+            parse_value(config_map, "MAX_PER_EPOCH_ACTIVATION_CHURN_LIMIT", mode)?
+        {
+            self.max_per_epoch_activation_churn_limit = max_per_epoch_activation_churn_limit;
+        }
+        if let Some(churn_limit_quotient) = parse_value(config_map, "CHURN_LIMIT_QUOTIENT", mode)? {
+            self.churn_limit_quotient = churn_limit_quotient;
+        }
+        if let Some(ejection_balance) = parse_value(config_map, "EJECTION_BALANCE", mode)? {
+            self.ejection_balance = ejection_balance;
+        }
+        if let Some(eth1_follow_distance) = parse_value(config_map, "ETH1_FOLLOW_DISTANCE", mode)? {
+            self.eth1_follow_distance = eth1_follow_distance;
+        }
+        if let Some(min_validator_withdrawability_delay) =
+            parse_value(config_map, "MIN_VALIDATOR_WITHDRAWABILITY_DELAY", mode)?
+        {
+            self.min_validator_withdrawability_delay = min_validator_withdrawability_delay;
+        }
+        if let Some(shard_committee_period) =
+            parse_value(config_map, "SHARD_COMMITTEE_PERIOD", mode)?
+        {
+            self.shard_committee_period = shard_committee_period;
+        }
+        if let Some(min_epochs_for_block_requests) =
+            parse_value(config_map, "MIN_EPOCHS_FOR_BLOCK_REQUESTS", mode)?
+        {
+            self.min_epochs_for_block_requests = min_epochs_for_block_requests;
+        }
+        if let Some(max_blobs_per_block) = parse_value(config_map, "MAX_BLOBS_PER_BLOCK", mode)? {
+            self.max_blobs_per_block = max_blobs_per_block;
+        }
+        if let Some(samples_per_slot) = parse_value(config_map, "SAMPLES_PER_SLOT", mode)? {
+            self.samples_per_slot = samples_per_slot;
+        }
+        if let Some(custody_requirement) = parse_value(config_map, "CUSTODY_REQUIREMENT", mode)? {
+            self.custody_requirement = custody_requirement;
+        }
+        if let Some(data_column_sidecar_subnet_count) =
+            parse_value(config_map, "DATA_COLUMN_SIDECAR_SUBNET_COUNT", mode)?
+        {
+            self.data_column_sidecar_subnet_count = data_column_sidecar_subnet_count;
+        }
+        if let Some(target_number_of_peers) =
+            parse_value(config_map, "TARGET_NUMBER_OF_PEERS", mode)?
+        {
+            self.target_number_of_peers = target_number_of_peers;
+        }
+        Ok(())
+    }
+
+    /// Adds these settings to `config_map` under their upstream keys.
+    pub fn extend_config_map(&self, config_map: &mut BTreeMap<String, String>) {
+        let entries = [
+            ("NUMBER_OF_VALIDATORS", self.number_of_validators.to_string()),
+            ("GENESIS_FORK_VERSION", self.genesis_fork_version.to_string()),
+            ("ALTAIR_FORK_VERSION", self.altair_fork_version.to_string()),
+            ("BELLATRIX_FORK_VERSION", self.bellatrix_fork_version.to_string()),
+            ("CAPELLA_FORK_VERSION", self.capella_fork_version.to_string()),
+            ("DENEB_FORK_VERSION", self.deneb_fork_version.to_string()),
+            ("ELECTRA_FORK_VERSION", self.electra_fork_version.to_string()),
+            ("EIP7594_FORK_VERSION", self.eip7594_fork_version.to_string()),
+            ("EIP7594_FORK_EPOCH", self.eip7594_fork_epoch.to_string()),
+            (
+                "MAX_PER_EPOCH_ACTIVATION_CHURN_LIMIT",
+                self.max_per_epoch_activation_churn_limit.to_string(),
+            ),
+            ("CHURN_LIMIT_QUOTIENT", self.churn_limit_quotient.to_string()),
+            ("EJECTION_BALANCE", self.ejection_balance.to_string()),
+            ("ETH1_FOLLOW_DISTANCE", self.eth1_follow_distance.to_string()),
+            (
+                "MIN_VALIDATOR_WITHDRAWABILITY_DELAY",
+                self.min_validator_withdrawability_delay.to_string(),
+            ),
+            ("SHARD_COMMITTEE_PERIOD", self.shard_committee_period.to_string()),
+            ("MIN_EPOCHS_FOR_BLOCK_REQUESTS", self.min_epochs_for_block_requests.to_string()),
+            ("MAX_BLOBS_PER_BLOCK", self.max_blobs_per_block.to_string()),
+            ("SAMPLES_PER_SLOT", self.samples_per_slot.to_string()),
+            ("CUSTODY_REQUIREMENT", self.custody_requirement.to_string()),
+            (
+                "DATA_COLUMN_SIDECAR_SUBNET_COUNT",
+                self.data_column_sidecar_subnet_count.to_string(),
+            ),
+            ("TARGET_NUMBER_OF_PEERS", self.target_number_of_peers.to_string()),
+        ];
+        for (key, value) in entries {
+            config_map.insert(key.to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_fork_versions() {
+        let version: ForkVersion = "0x10000038".parse().unwrap();
+        assert_eq!(version, ForkVersion([0x10, 0, 0, 0x38]));
+        assert_eq!(version.to_string(), "0x10000038");
+        assert!("0x100000".parse::<ForkVersion>().is_err());
+    }
+
+    #[test]
+    fn test_apply_keeps_unconfigured_defaults() {
+        let mut config_map = HashMap::new();
+        config_map.insert("NUMBER_OF_VALIDATORS".to_string(), "128".to_string());
+        config_map.insert("CHURN_LIMIT_QUOTIENT".to_string(), "".to_string());
+
+        let mut params = ConsensusParams::default();
+        params.apply(&config_map, ConfigMode::Strict).unwrap();
+        assert_eq!(params.number_of_validators, 128);
+        assert_eq!(params.churn_limit_quotient, 65536);
+    }
+}
@@ -0,0 +1,32 @@
+use serde_json::{json, Value};
+
+/// A geth-style genesis for tests, with every pre-merge fork at block 0 and shanghai and cancun at
+/// genesis. Entries of `config` are added to or replace the defaults of the `config` object.
+pub(crate) fn geth_genesis(timestamp: u64, config: Value, alloc: Value) -> Value {
+    let mut genesis = json!({
+        "config": {
+            "chainId": 1337, "homesteadBlock": 0, "eip150Block": 0, "eip155Block": 0,
+            "eip158Block": 0, "byzantiumBlock": 0, "constantinopleBlock": 0,
+            "petersburgBlock": 0, "istanbulBlock": 0, "berlinBlock": 0, "londonBlock": 0,
+            "preMergeForkBlock": 0, "terminalTotalDifficulty": 0, "shanghaiTime": 0,
+            "cancunTime": 0, "depositContractAddress": "0x4242424242424242424242424242424242424242",
+            "ethash": {}
+        },
+        "alloc": alloc,
+        "coinbase": "0x0000000000000000000000000000000000000000",
+        "baseFeePerGas": "0x3B9ACA00",
+        "difficulty": "0x01",
+        "extraData": "",
+        "gasLimit": "0x17D7840",
+        "nonce": "0x1234",
+        "mixhash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+        "parentHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+        "timestamp": timestamp.to_string()
+    });
+    if let Value::Object(config) = config {
+        for (key, value) in config {
+            genesis["config"][key] = value;
+        }
+    }
+    genesis
+}
@@ -0,0 +1,369 @@
+use anyhow::{bail, Context, Result};
+use std::{any::Any, fmt, path::Path};
+
+use super::{
+    accounts::PremineAccount, allocation::AllocOverride, besu_generation::BesuGenesisConfig,
+    chainspec_generation::ChainspecGenesisConfig, genesis_config::GenesisConfig,
+    geth_generation::GethGenesisConfig, network_genesis::NetworkGenesis,
+    serializabe_to_file::Genesis,
+};
+
+/// EL forks scheduled by the config on top of the template of a genesis format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fork {
+    Shanghai,
+    Cancun,
+    Prague,
+    PragueEof,
+}
+
+impl Fork {
+    pub const ALL: [Fork; 4] = [Fork::Shanghai, Fork::Cancun, Fork::Prague, Fork::PragueEof];
+
+    /// Forks scheduled by `genesis_config`, in activation order.
+    pub fn scheduled(genesis_config: &GenesisConfig) -> Vec<Fork> {
+        Fork::ALL
+            .into_iter()
+            .filter(|fork| fork.activation_epoch(genesis_config).is_some())
+            .collect()
+    }
+
+    /// The CL epoch the fork activates at, `None` if it is not scheduled.
+    pub fn activation_epoch(&self, genesis_config: &GenesisConfig) -> Option<u64> {
+        match self {
+            Fork::Shanghai => genesis_config.capella_fork_epoch,
+            Fork::Cancun => genesis_config.deneb_fork_epoch,
+            Fork::Prague => genesis_config.electra_fork_epoch,
+            Fork::PragueEof => genesis_config.eof_activation_epoch,
+        }
+    }
+
+    /// Name of the CL fork activating at the same epoch.
+    pub fn cl_name(&self) -> &'static str {
+        match self {
+            Fork::Shanghai => "capella",
+            Fork::Cancun => "deneb",
+            Fork::Prague => "electra",
+            Fork::PragueEof => "eof",
+        }
+    }
+
+    /// Config key scheduling the fork, read as a `ForkSchedule`.
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            Fork::Shanghai => "CAPELLA_FORK_EPOCH",
+            Fork::Cancun => "DENEB_FORK_EPOCH",
+            Fork::Prague => "ELECTRA_FORK_EPOCH",
+            Fork::PragueEof => "EOF_ACTIVATION_EPOCH",
+        }
+    }
+
+    /// The fork named `name`, either its EL name such as `cancun` or its CL name such as `deneb`.
+    pub fn from_name(name: &str) -> Option<Fork> {
+        Fork::ALL
+            .into_iter()
+            .find(|fork| fork.to_string() == name || fork.cl_name() == name)
+    }
+}
+
+impl fmt::Display for Fork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fork::Shanghai => write!(f, "shanghai"),
+            Fork::Cancun => write!(f, "cancun"),
+            Fork::Prague => write!(f, "prague"),
+            Fork::PragueEof => write!(f, "prague-eof"),
+        }
+    }
+}
+
+/// A genesis created by a registered format, downcast with `as_any` to inspect or modify it.
+pub trait GeneratedGenesis: Any {
+    fn to_json(&self) -> serde_json::Result<Vec<u8>>;
+    fn alloc_overrides(&self) -> &[AllocOverride];
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<G: Genesis + 'static> GeneratedGenesis for G {
+    fn to_json(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(self)
+    }
+
+    fn alloc_overrides(&self) -> &[AllocOverride] {
+        Genesis::alloc_overrides(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+type CreateGenesis = fn(&GenesisConfig, &[PremineAccount]) -> Result<Box<dyn GeneratedGenesis>>;
+
+/// The name, file name, clients and supported forks of a `Genesis` implementation.
+#[derive(Clone, Copy)]
+pub struct GenesisFormat {
+    pub name: &'static str,
+    pub file_name: &'static str,
+    pub clients: &'static [&'static str],
+    pub supported_forks: &'static [Fork],
+    create: CreateGenesis,
+    read: fn(&Path) -> Result<Box<dyn GeneratedGenesis>>,
+    read_network: fn(&Path) -> Result<NetworkGenesis>,
+}
+
+impl GenesisFormat {
+    pub fn of<G: Genesis + 'static>() -> Self {
+        GenesisFormat {
+            name: G::NAME,
+            file_name: G::FILE_NAME,
+            clients: G::CLIENTS,
+            supported_forks: G::SUPPORTED_FORKS,
+            create: create::<G>,
+            read: read::<G>,
+            read_network: read_network::<G>,
+        }
+    }
+
+    /// Reads an existing genesis file of this format.
+    pub fn read_genesis(&self, path: &Path) -> Result<Box<dyn GeneratedGenesis>> {
+        (self.read)(path)
+            .with_context(|| format!("Cannot read {} as {} genesis", path.display(), self.name))
+    }
+
+    /// Reads an existing genesis file of this format into the shared model.
+    pub fn read_network(&self, path: &Path) -> Result<NetworkGenesis> {
+        (self.read_network)(path)
+            .with_context(|| format!("Cannot read {} as {} genesis", path.display(), self.name))
+    }
+
+    pub fn create_genesis(
+        &self,
+        genesis_config: &GenesisConfig,
+        premine: &[PremineAccount],
+    ) -> Result<Box<dyn GeneratedGenesis>> {
+        for fork in Fork::scheduled(genesis_config) {
+            if !self.supported_forks.contains(&fork) {
+                bail!("{} genesis does not support the {} fork", self.name, fork);
+            }
+        }
+        (self.create)(genesis_config, premine)
+            .with_context(|| format!("Failed to create {} genesis", self.name))
+    }
+}
+
+fn create<G: Genesis + 'static>(
+    genesis_config: &GenesisConfig,
+    premine: &[PremineAccount],
+) -> Result<Box<dyn GeneratedGenesis>> {
+    Ok(Box::new(G::create_genesis(genesis_config, premine)?))
+}
+
+fn read<G: Genesis + 'static>(path: &Path) -> Result<Box<dyn GeneratedGenesis>> {
+    Ok(Box::new(G::read_from_file(path)?))
+}
+
+fn read_network<G: Genesis + 'static>(path: &Path) -> Result<NetworkGenesis> {
+    Ok(G::read_from_file(path)?.into())
+}
+
+/// The genesis formats to generate, in registration order. The default registry holds the
+/// built-in geth, chainspec and besu formats; other crates add their own with `register`.
+#[derive(Clone)]
+pub struct FormatRegistry {
+    formats: Vec<GenesisFormat>,
+}
+
+impl FormatRegistry {
+    /// A registry without any format.
+    pub fn empty() -> Self {
+        FormatRegistry {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Adds `G`, replacing a registered format of the same name.
+    pub fn register<G: Genesis + 'static>(mut self) -> Self {
+        let format = GenesisFormat::of::<G>();
+        match self
+            .formats
+            .iter_mut()
+            .find(|registered| registered.name == format.name)
+        {
+            Some(registered) => *registered = format,
+            None => self.formats.push(format),
+        }
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&GenesisFormat> {
+        self.formats.iter().find(|format| format.name == name)
+    }
+
+    pub fn formats(&self) -> impl Iterator<Item = &GenesisFormat> {
+        self.formats.iter()
+    }
+
+    /// Reads the genesis file at `path` with the format written to that file name, or else
+    /// with the first format that can parse it.
+    pub fn detect(&self, path: &Path) -> Result<(&GenesisFormat, Box<dyn GeneratedGenesis>)> {
+        let file_name = path.file_name().and_then(|name| name.to_str());
+        if let Some(format) = self
+            .formats
+            .iter()
+            .find(|format| Some(format.file_name) == file_name)
+        {
+            return Ok((format, format.read_genesis(path)?));
+        }
+        for format in &self.formats {
+            if let Ok(genesis) = format.read_genesis(path) {
+                return Ok((format, genesis));
+            }
+        }
+        bail!(
+            "{} is not a genesis file of any known format",
+            path.display()
+        )
+    }
+
+    /// The formats read by any of `clients`, or every format if `clients` is empty.
+    pub fn select(&self, clients: &[String]) -> Result<FormatRegistry> {
+        if clients.is_empty() {
+            return Ok(self.clone());
+        }
+        let is_selected = |format: &GenesisFormat| {
+            format
+                .clients
+                .iter()
+                .any(|client| clients.iter().any(|selected| selected == client))
+        };
+        for client in clients {
+            if !self
+                .formats
+                .iter()
+                .any(|format| format.clients.contains(&client.as_str()))
+            {
+                let known: Vec<&str> = self
+                    .formats
+                    .iter()
+                    .flat_map(|format| format.clients.iter().copied())
+                    .collect();
+                bail!(
+                    "no EL genesis format for client {}, known clients: {}",
+                    client,
+                    known.join(", ")
+                );
+            }
+        }
+        Ok(FormatRegistry {
+            formats: self.formats.iter().copied().filter(is_selected).collect(),
+        })
+    }
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        FormatRegistry::empty()
+            .register::<GethGenesisConfig>()
+            .register::<ChainspecGenesisConfig>()
+            .register::<BesuGenesisConfig>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::{
+        allocation::Alloc, config::Config, network_genesis::GenesisHeader,
+        serializabe_to_file::SerializableToFile,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct ShanghaiOnlyGenesis {
+        chain_id: u32,
+    }
+
+    impl SerializableToFile for ShanghaiOnlyGenesis {}
+
+    impl From<ShanghaiOnlyGenesis> for NetworkGenesis {
+        fn from(genesis: ShanghaiOnlyGenesis) -> Self {
+            NetworkGenesis {
+                config: Config::new(genesis.chain_id, String::new()),
+                alloc: Alloc::new(),
+                header: GenesisHeader::default(),
+            }
+        }
+    }
+
+    impl Genesis for ShanghaiOnlyGenesis {
+        const NAME: &'static str = "geth";
+        const FILE_NAME: &'static str = "shanghai.json";
+        const CLIENTS: &'static [&'static str] = &["geth"];
+        const SUPPORTED_FORKS: &'static [Fork] = &[Fork::Shanghai];
+
+        fn create_genesis(
+            genesis_config: &GenesisConfig,
+            _premine: &[PremineAccount],
+        ) -> Result<Self> {
+            Ok(ShanghaiOnlyGenesis {
+                chain_id: genesis_config.chain_id,
+            })
+        }
+
+        fn alloc_overrides(&self) -> &[AllocOverride] {
+            &[]
+        }
+    }
+
+    #[test]
+    fn test_registered_format_replaces_builtin_of_same_name() {
+        let registry = FormatRegistry::default().register::<ShanghaiOnlyGenesis>();
+
+        let names: Vec<_> = registry.formats().map(|format| format.name).collect();
+        assert_eq!(names, ["geth", "chainspec", "besu"]);
+        assert_eq!(registry.get("geth").unwrap().file_name, "shanghai.json");
+    }
+
+    #[test]
+    fn test_selects_formats_by_client() {
+        let registry = FormatRegistry::default();
+
+        let selected = registry
+            .select(&["reth".to_string(), "nethermind".to_string()])
+            .unwrap();
+        let names: Vec<_> = selected.formats().map(|format| format.name).collect();
+        assert_eq!(names, ["geth", "chainspec"]);
+        assert_eq!(registry.select(&[]).unwrap().formats().count(), 3);
+        assert!(registry.select(&["parity".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unsupported_forks() {
+        let format = GenesisFormat::of::<ShanghaiOnlyGenesis>();
+        let mut genesis_config = GenesisConfig {
+            chain_id: 1337,
+            capella_fork_epoch: Some(0),
+            ..Default::default()
+        };
+
+        let genesis = format.create_genesis(&genesis_config, &[]).unwrap();
+        let genesis = genesis
+            .as_any()
+            .downcast_ref::<ShanghaiOnlyGenesis>()
+            .unwrap();
+        assert_eq!(genesis.chain_id, 1337);
+
+        genesis_config.deneb_fork_epoch = Some(1);
+        let error = format.create_genesis(&genesis_config, &[]).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "geth genesis does not support the cancun fork"
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::marker::PhantomData;
+
+use anyhow::{Context, Result};
+use ethers::signers::Signer;
+
+use super::{
+    accounts::PremineAccount,
+    allocation::{AllocMerger, AllocOverride, AllocSource, Allocation},
+    genesis_config::{get_activation_epoch, GenesisConfig},
+    network_genesis::NetworkGenesis,
+    serializabe_to_file::Genesis,
+};
+
+/// A genesis format generated from a template in the `../data/<network>` folder of its chain.
+pub trait TemplateGenesis: Genesis + From<NetworkGenesis> {
+    /// Template file read from the network folder, e.g. `geth_genesis.json`.
+    const TEMPLATE: &'static str;
+
+    /// Stores the alloc entries that replaced or were merged into an existing entry.
+    fn set_alloc_overrides(&mut self, alloc_overrides: Vec<AllocOverride>);
+}
+
+/// Creates the genesis of format `G`, allocating the `premine` accounts and scheduling the
+/// forks of `genesis_config` on top of the format's template.
+pub fn create_genesis<G: TemplateGenesis>(
+    genesis_config: &GenesisConfig,
+    premine: &[PremineAccount],
+) -> Result<G> {
+    Ok(GenesisBuilder::<G>::new(genesis_config)?
+        .with_premine(premine)?
+        .with_premine_addrs(genesis_config)?
+        .with_additional_preloaded_contracts(genesis_config)?
+        .with_capella_fork_epoch(genesis_config)
+        .with_deneb_fork_epoch(genesis_config)
+        .with_electra_fork_epoch(genesis_config)
+        .with_eof_activation_epoch(genesis_config)
+        .build())
+}
+
+/// Path of the template of format `G` for `chain_id`; unknown chains use the devnet template.
+fn template_path<G: TemplateGenesis>(chain_id: u32) -> String {
+    let network = match chain_id {
+        1 => "mainnet",
+        11155111 => "sepolia",
+        17000 => "holesky",
+        _ => "devnet",
+    };
+    format!("../data/{}/{}", network, G::TEMPLATE)
+}
+
+struct GenesisBuilder<G> {
+    genesis: NetworkGenesis,
+    merger: AllocMerger,
+    format: PhantomData<G>,
+}
+
+impl<G: TemplateGenesis> GenesisBuilder<G> {
+    fn new(genesis_config: &GenesisConfig) -> Result<Self> {
+        let path = template_path::<G>(genesis_config.chain_id);
+        let template = G::read_from_file(&path)
+            .with_context(|| format!("Cannot read the {} template {}", G::NAME, path))?;
+        let mut genesis: NetworkGenesis = template.into();
+        if path.starts_with("../data/devnet/") {
+            // todo replace values
+            genesis.config.chain_id = genesis_config.chain_id;
+            genesis.config.deposit_contract_address =
+                genesis_config.deposit_contract_address.clone();
+            genesis.header.timestamp = genesis_config.genesis_timestamp.to_string();
+            if let Some(genesis_gaslimit) = genesis_config.genesis_gaslimit {
+                genesis.header.gas_limit = format!("{:#x}", genesis_gaslimit);
+            }
+        }
+        Ok(GenesisBuilder {
+            genesis,
+            merger: AllocMerger::new(genesis_config.alloc_conflict_policy),
+            format: PhantomData,
+        })
+    }
+
+    fn with_premine(mut self, premine: &[PremineAccount]) -> Result<Self> {
+        for premine in premine {
+            self.merger.insert(
+                &mut self.genesis.alloc,
+                premine.wallet.address(),
+                Allocation::with_balance(premine.balance),
+                AllocSource::Premine,
+            )?;
+        }
+        Ok(self)
+    }
+
+    fn with_premine_addrs(mut self, genesis_config: &GenesisConfig) -> Result<Self> {
+        for (addr, account) in &genesis_config.el_premine_addrs {
+            self.merger.insert(
+                &mut self.genesis.alloc,
+                *addr,
+                account.clone(),
+                AllocSource::PremineAddrs,
+            )?;
+        }
+        Ok(self)
+    }
+
+    fn with_additional_preloaded_contracts(
+        mut self,
+        genesis_config: &GenesisConfig,
+    ) -> Result<Self> {
+        for (addr, account) in &genesis_config.additional_preloaded_contracts {
+            self.merger.insert(
+                &mut self.genesis.alloc,
+                *addr,
+                account.clone(),
+                AllocSource::PreloadedContracts,
+            )?;
+        }
+        Ok(self)
+    }
+
+    fn with_capella_fork_epoch(mut self, genesis_config: &GenesisConfig) -> Self {
+        if let Some(capella_fork_epoch) = genesis_config.capella_fork_epoch {
+            self.genesis.config.shanghai_time = activation_time(genesis_config, capella_fork_epoch);
+        }
+        self
+    }
+
+    fn with_deneb_fork_epoch(mut self, genesis_config: &GenesisConfig) -> Self {
+        if let Some(deneb_fork_epoch) = genesis_config.deneb_fork_epoch {
+            self.genesis.config.cancun_time = activation_time(genesis_config, deneb_fork_epoch);
+        }
+        self
+    }
+
+    fn with_electra_fork_epoch(mut self, genesis_config: &GenesisConfig) -> Self {
+        if let Some(electra_fork_epoch) = genesis_config.electra_fork_epoch {
+            self.genesis.config.prague_time =
+                Some(activation_time(genesis_config, electra_fork_epoch));
+        }
+        self
+    }
+
+    fn with_eof_activation_epoch(mut self, genesis_config: &GenesisConfig) -> Self {
+        if let Some(eof_activation_epoch) = genesis_config.eof_activation_epoch {
+            self.genesis.config.prague_eoftime =
+                Some(activation_time(genesis_config, eof_activation_epoch));
+        }
+        self
+    }
+
+    fn build(self) -> G {
+        let mut genesis = G::from(self.genesis);
+        genesis.set_alloc_overrides(self.merger.into_overrides());
+        genesis
+    }
+}
+
+fn activation_time(genesis_config: &GenesisConfig, epoch: u64) -> u64 {
+    get_activation_epoch(
+        genesis_config.genesis_timestamp,
+        genesis_config.genesis_delay,
+        &genesis_config.preset_base,
+        genesis_config.slot_duration_in_seconds,
+        epoch,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::el::{genesis_config::GenesisConfigBuilder, geth_generation::GethGenesisConfig};
+
+    #[test]
+    #[serial]
+    fn test_missing_template_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let work = dir.path().join("work");
+        std::fs::create_dir(&work).unwrap();
+        let genesis_config = GenesisConfigBuilder::new()
+            .preset_base("minimal".to_string())
+            .chain_id(1337)
+            .deposit_contract_address("0x4242424242424242424242424242424242424242".to_string())
+            .mnemonic("test test test test test test test test test test test junk".to_string())
+            .genesis_timestamp(1700000000)
+            .genesis_delay(60)
+            .slot_duration_in_seconds(6)
+            .build()
+            .unwrap();
+
+        let cwd = env::current_dir().unwrap();
+        env::set_current_dir(&work).unwrap();
+        let result = create_genesis::<GethGenesisConfig>(&genesis_config, &[]);
+        env::set_current_dir(cwd).unwrap();
+
+        let error = format!("{:#}", result.err().unwrap());
+        assert!(
+            error.starts_with("Cannot read the geth template ../data/devnet/geth_genesis.json"),
+            "{}",
+            error
+        );
+    }
+}
@@ -1,7 +1,45 @@
-use anyhow::{Context, Result};
-use std::collections::HashMap;
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    str::FromStr,
+};
 
-use super::config::Allocation;
+use ethers::{
+    types::{Address, U256},
+    utils::to_checksum,
+};
+use serde_json::Value;
+
+use super::allocation::{parse_alloc, parse_balance, Alloc, Allocation, ConflictPolicy};
+use super::config_keys::{check_unknown_keys, check_unsupported_keys, ConfigMode};
+use super::consensus_params::{ConsensusParams, CL_CLIENTS, FAR_FUTURE_EPOCH};
+use super::format_registry::Fork;
+use crate::timeline::{format_utc, parse_duration, parse_utc_time};
+
+pub const DEFAULT_PREMINE_COUNT: u32 = 21;
+pub const DEFAULT_PREMINE_BALANCE_ETH: u64 = 1_000_000_000;
+
+fn default_premine_balance() -> U256 {
+    U256::from(DEFAULT_PREMINE_BALANCE_ETH) * U256::exp10(18)
+}
+
+/// Values `from_hashmap` falls back to when a key is not configured, in config key form.
+pub fn builtin_defaults() -> Vec<(String, String)> {
+    [
+        ("CONFIG_MODE", ConfigMode::from_environment().to_string()),
+        ("EL_PREMINE_COUNT", DEFAULT_PREMINE_COUNT.to_string()),
+        ("EL_PREMINE_BALANCE", format!("{}ETH", DEFAULT_PREMINE_BALANCE_ETH)),
+        ("EL_PREMINE_START_INDEX", "0".to_string()),
+        ("EL_PREMINE_KEYSTORES", "false".to_string()),
+        ("ALLOC_CONFLICT_POLICY", ConflictPolicy::default().to_string()),
+        ("EL_CLIENTS", String::new()),
+        ("CL_CLIENTS", String::new()),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect()
+}
 
 #[derive(Default)]
 pub struct GenesisConfig {
@@ -9,32 +47,135 @@ pub struct GenesisConfig {
     pub chain_id: u32,
     pub deposit_contract_address: String,
     pub mnemonic: String,
-    pub el_premine: std::collections::HashMap<String, String>,
-    pub el_premine_addrs: std::collections::HashMap<String, Allocation>,
-    pub additional_preloaded_contracts: std::collections::HashMap<String, Allocation>,
+    pub el_premine: BTreeMap<String, String>,
+    pub el_premine_count: u32,
+    pub el_premine_balance: U256,
+    pub el_premine_start_index: u32,
+    pub el_premine_keystores: bool,
+    pub el_premine_keystore_password: String,
+    pub el_premine_addrs: Alloc,
+    pub additional_preloaded_contracts: Alloc,
+    pub alloc_conflict_policy: ConflictPolicy,
     pub genesis_timestamp: u64,
     pub genesis_delay: u64,
     pub genesis_gaslimit: Option<u64>,
     pub slot_duration_in_seconds: u64,
+    pub capella_fork_epoch: Option<u64>,
+    pub deneb_fork_epoch: Option<u64>,
     pub electra_fork_epoch: Option<u64>,
     pub eof_activation_epoch: Option<u64>,
+    /// EL clients to generate genesis files for, every registered format if empty.
+    pub el_clients: Vec<String>,
+    /// CL clients to generate config for, every client if empty.
+    pub cl_clients: Vec<String>,
+    /// Seed of the randomness in generated files such as keystores, random if unset.
+    pub seed: Option<u64>,
+    pub consensus: ConsensusParams,
+}
+
+impl GenesisConfig {
+    /// The config keys `GenesisConfigBuilder::from_hashmap` reads back into this config.
+    /// Unset optional values are written as empty strings.
+    pub fn to_config_map(&self) -> Result<BTreeMap<String, String>> {
+        let optional = |value: Option<u64>| value.map(|value| value.to_string()).unwrap_or_default();
+        let mut config_map: BTreeMap<String, String> = [
+            ("PRESET_BASE", self.preset_base.clone()),
+            ("CHAIN_ID", self.chain_id.to_string()),
+            ("DEPOSIT_CONTRACT_ADDRESS", self.deposit_contract_address.clone()),
+            ("EL_AND_CL_MNEMONIC", self.mnemonic.clone()),
+            ("EL_PREMINE_PATHS", serde_json::to_string(&self.el_premine)?),
+            ("EL_PREMINE_COUNT", self.el_premine_count.to_string()),
+            ("EL_PREMINE_BALANCE", self.el_premine_balance.to_string()),
+            ("EL_PREMINE_START_INDEX", self.el_premine_start_index.to_string()),
+            ("EL_PREMINE_KEYSTORES", self.el_premine_keystores.to_string()),
+            ("EL_PREMINE_KEYSTORE_PASSWORD", self.el_premine_keystore_password.clone()),
+            ("EL_PREMINE_ADDRS", allocation_to_string(&self.el_premine_addrs)?),
+            (
+                "ADDITIONAL_PRELOADED_CONTRACTS",
+                allocation_to_string(&self.additional_preloaded_contracts)?,
+            ),
+            ("ALLOC_CONFLICT_POLICY", self.alloc_conflict_policy.to_string()),
+            ("GENESIS_TIMESTAMP", self.genesis_timestamp.to_string()),
+            ("GENESIS_DELAY", self.genesis_delay.to_string()),
+            ("GENESIS_GASLIMIT", optional(self.genesis_gaslimit)),
+            ("SLOT_DURATION_IN_SECONDS", self.slot_duration_in_seconds.to_string()),
+            ("EL_CLIENTS", self.el_clients.join(",")),
+            ("CL_CLIENTS", self.cl_clients.join(",")),
+            ("SEED", optional(self.seed)),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+        for fork in Fork::ALL {
+            config_map.insert(
+                fork.config_key().to_string(),
+                optional(fork.activation_epoch(self)),
+            );
+        }
+        self.consensus.extend_config_map(&mut config_map);
+        Ok(config_map)
+    }
+
+    /// CL genesis time, the EL genesis timestamp plus the genesis delay.
+    pub fn genesis_time(&self) -> u64 {
+        self.genesis_timestamp + self.genesis_delay
+    }
+
+    pub fn seconds_per_epoch(&self) -> u64 {
+        slots_per_epoch(&self.preset_base) * self.slot_duration_in_seconds
+    }
+
+    /// Timestamp at which `epoch` starts on this network.
+    pub fn activation_time(&self, epoch: u64) -> u64 {
+        get_activation_epoch(
+            self.genesis_timestamp,
+            self.genesis_delay,
+            &self.preset_base,
+            self.slot_duration_in_seconds,
+            epoch,
+        )
+    }
+
+    /// Timestamp at which `epoch` starts, `None` if it is past the largest `u64` timestamp.
+    pub fn checked_activation_time(&self, epoch: u64) -> Option<u64> {
+        checked_activation_time(
+            self.genesis_timestamp,
+            self.genesis_delay,
+            &self.preset_base,
+            self.slot_duration_in_seconds,
+            epoch,
+        )
+    }
 }
 
 #[derive(Default)]
 pub struct GenesisConfigBuilder {
+    config_mode: Option<ConfigMode>,
     preset_base: Option<String>,
     chain_id: Option<u32>,
     deposit_contract_address: Option<String>,
     mnemonic: Option<String>,
-    el_premine: HashMap<String, String>,
-    el_premine_addrs: HashMap<String, Allocation>,
-    additional_preloaded_contracts: HashMap<String, Allocation>,
+    el_premine: BTreeMap<String, String>,
+    el_premine_count: Option<u32>,
+    el_premine_balance: Option<U256>,
+    el_premine_start_index: Option<u32>,
+    el_premine_keystores: bool,
+    el_premine_keystore_password: String,
+    el_premine_addrs: Alloc,
+    additional_preloaded_contracts: Alloc,
+    alloc_conflict_policy: ConflictPolicy,
     genesis_timestamp: Option<u64>,
     genesis_delay: Option<u64>,
     genesis_gaslimit: Option<u64>,
     slot_duration_in_seconds: Option<u64>,
-    electra_fork_epoch: Option<u64>,
-    eof_activation_epoch: Option<u64>,
+    capella_fork_epoch: Option<ForkSchedule>,
+    deneb_fork_epoch: Option<ForkSchedule>,
+    electra_fork_epoch: Option<ForkSchedule>,
+    eof_activation_epoch: Option<ForkSchedule>,
+    el_clients: Vec<String>,
+    cl_clients: Vec<String>,
+    seed: Option<u64>,
+    consensus: ConsensusParams,
 }
 
 impl GenesisConfigBuilder {
@@ -42,12 +183,22 @@ impl GenesisConfigBuilder {
         Self::default()
     }
 
+    /// Reads every known key from `config_map`. Unknown keys and malformed numbers fail in
+    /// strict mode and are reported and ignored in warn mode, see `ConfigMode`.
     pub fn from_hashmap(mut self, config_map: HashMap<String, String>) -> Result<Self> {
+        let mode = match (self.config_mode, config_map.get("CONFIG_MODE")) {
+            (Some(mode), _) => mode,
+            (None, Some(mode)) => mode.parse()?,
+            (None, None) => ConfigMode::from_environment(),
+        };
+        check_unknown_keys(&config_map, mode)?;
+        check_unsupported_keys(&config_map, mode)?;
+
         if let Some(preset_base) = config_map.get("PRESET_BASE") {
             self.preset_base = Some(preset_base.clone());
         }
-        if let Some(chain_id) = config_map.get("CHAIN_ID") {
-            self.chain_id = chain_id.parse().ok();
+        if let Some(chain_id) = parse_value(&config_map, "CHAIN_ID", mode)? {
+            self.chain_id = Some(chain_id);
         }
         if let Some(deposit_contract_address) = config_map.get("DEPOSIT_CONTRACT_ADDRESS") {
             self.deposit_contract_address = Some(deposit_contract_address.clone());
@@ -55,38 +206,102 @@ impl GenesisConfigBuilder {
         if let Some(mnemonic) = config_map.get("EL_AND_CL_MNEMONIC") {
             self.mnemonic = Some(mnemonic.clone());
         }
+        // Derivation paths premined explicitly, as a JSON object of path to balance.
+        if let Some(el_premine) = parse_value_with(&config_map, "EL_PREMINE_PATHS", mode, |value| {
+            Ok(serde_json::from_str::<BTreeMap<String, String>>(value)?)
+        })? {
+            self.el_premine.extend(el_premine);
+        }
+        if let Some(el_premine_count) = parse_value(&config_map, "EL_PREMINE_COUNT", mode)? {
+            self.el_premine_count = Some(el_premine_count);
+        }
+        if let Some(el_premine_balance) =
+            parse_value_with(&config_map, "EL_PREMINE_BALANCE", mode, parse_balance)?
+        {
+            self.el_premine_balance = Some(el_premine_balance);
+        }
+        if let Some(el_premine_start_index) =
+            parse_value(&config_map, "EL_PREMINE_START_INDEX", mode)?
+        {
+            self.el_premine_start_index = Some(el_premine_start_index);
+        }
+        if let Some(el_premine_keystores) = parse_value(&config_map, "EL_PREMINE_KEYSTORES", mode)? {
+            self.el_premine_keystores = el_premine_keystores;
+        }
+        if let Some(el_premine_keystore_password) = config_map.get("EL_PREMINE_KEYSTORE_PASSWORD") {
+            self.el_premine_keystore_password = el_premine_keystore_password.clone();
+        }
         if let Some(el_premine_addrs) = config_map.get("EL_PREMINE_ADDRS") {
-            self.el_premine_addrs = parse_allocation(&el_premine_addrs)?;
+            self.el_premine_addrs = parse_allocation(el_premine_addrs)
+                .context("Invalid EL_PREMINE_ADDRS")?;
         }
 
         if let Some(additional_preloaded_contracts) =
             config_map.get("ADDITIONAL_PRELOADED_CONTRACTS")
         {
             self.additional_preloaded_contracts =
-                parse_allocation(&additional_preloaded_contracts)?;
+                parse_allocation(additional_preloaded_contracts)
+                    .context("Invalid ADDITIONAL_PRELOADED_CONTRACTS")?;
+        }
+        if let Some(alloc_conflict_policy) = config_map.get("ALLOC_CONFLICT_POLICY") {
+            self.alloc_conflict_policy = alloc_conflict_policy.parse()?;
         }
 
-        if let Some(genesis_timestamp) = config_map.get("GENESIS_TIMESTAMP") {
-            self.genesis_timestamp = genesis_timestamp.parse().ok();
+        if let Some(genesis_timestamp) = parse_value(&config_map, "GENESIS_TIMESTAMP", mode)? {
+            self.genesis_timestamp = Some(genesis_timestamp);
         }
-        if let Some(genesis_delay) = config_map.get("GENESIS_DELAY") {
-            self.genesis_delay = genesis_delay.parse().ok();
+        if let Some(genesis_delay) = parse_value(&config_map, "GENESIS_DELAY", mode)? {
+            self.genesis_delay = Some(genesis_delay);
         }
-        if let Some(genesis_gaslimit) = config_map.get("GENESIS_GASLIMIT") {
-            self.genesis_gaslimit = genesis_gaslimit.parse().ok();
+        if let Some(genesis_gaslimit) = parse_value(&config_map, "GENESIS_GASLIMIT", mode)? {
+            self.genesis_gaslimit = Some(genesis_gaslimit);
         }
-        if let Some(slot_duration_in_seconds) = config_map.get("SLOT_DURATION_IN_SECONDS") {
-            self.slot_duration_in_seconds = slot_duration_in_seconds.parse().ok();
+        if let Some(slot_duration_in_seconds) = parse_value(&config_map, "SLOT_DURATION_IN_SECONDS", mode)? {
+            self.slot_duration_in_seconds = Some(slot_duration_in_seconds);
         }
-        if let Some(electra_fork_epoch) = config_map.get("ELECTRA_FORK_EPOCH") {
-            self.electra_fork_epoch = electra_fork_epoch.parse().ok();
+        for fork in Fork::ALL {
+            if let Some(schedule) = parse_fork(&config_map, fork.config_key(), mode)? {
+                *self.fork_schedule(fork) = schedule;
+            }
         }
-        if let Some(eof_activation_epoch) = config_map.get("EOF_ACTIVATION_EPOCH") {
-            self.eof_activation_epoch = eof_activation_epoch.parse().ok();
+        if let Some(el_clients) = config_map.get("EL_CLIENTS") {
+            self.el_clients = parse_clients(el_clients);
         }
+        if let Some(cl_clients) = config_map.get("CL_CLIENTS") {
+            self.cl_clients = parse_clients(cl_clients);
+            if let Some(unknown) = self
+                .cl_clients
+                .iter()
+                .find(|client| !CL_CLIENTS.contains(&client.as_str()))
+            {
+                bail!(
+                    "unknown CL client {}, known clients: {}",
+                    unknown,
+                    CL_CLIENTS.join(", ")
+                );
+            }
+        }
+        if let Some(seed) = parse_value(&config_map, "SEED", mode)? {
+            self.seed = Some(seed);
+        }
+        self.consensus.apply(&config_map, mode)?;
         Ok(self)
     }
 
+    fn fork_schedule(&mut self, fork: Fork) -> &mut Option<ForkSchedule> {
+        match fork {
+            Fork::Shanghai => &mut self.capella_fork_epoch,
+            Fork::Cancun => &mut self.deneb_fork_epoch,
+            Fork::Prague => &mut self.electra_fork_epoch,
+            Fork::PragueEof => &mut self.eof_activation_epoch,
+        }
+    }
+
+    pub fn config_mode(mut self, config_mode: ConfigMode) -> Self {
+        self.config_mode = Some(config_mode);
+        self
+    }
+
     pub fn preset_base(mut self, preset_base: String) -> Self {
         self.preset_base = Some(preset_base);
         self
@@ -112,16 +327,31 @@ impl GenesisConfigBuilder {
         self
     }
 
-    pub fn el_premine_addrs(mut self, key: String, value: Allocation) -> Self {
+    pub fn el_premine_keystores(mut self, el_premine_keystores: bool) -> Self {
+        self.el_premine_keystores = el_premine_keystores;
+        self
+    }
+
+    pub fn el_premine_keystore_password(mut self, el_premine_keystore_password: String) -> Self {
+        self.el_premine_keystore_password = el_premine_keystore_password;
+        self
+    }
+
+    pub fn el_premine_addrs(mut self, key: Address, value: Allocation) -> Self {
         self.el_premine_addrs.insert(key, value);
         self
     }
 
-    pub fn additional_preloaded_contracts(mut self, key: String, value: Allocation) -> Self {
+    pub fn additional_preloaded_contracts(mut self, key: Address, value: Allocation) -> Self {
         self.additional_preloaded_contracts.insert(key, value);
         self
     }
 
+    pub fn alloc_conflict_policy(mut self, alloc_conflict_policy: ConflictPolicy) -> Self {
+        self.alloc_conflict_policy = alloc_conflict_policy;
+        self
+    }
+
     pub fn genesis_timestamp(mut self, genesis_timestamp: u64) -> Self {
         self.genesis_timestamp = Some(genesis_timestamp);
         self
@@ -142,94 +372,316 @@ impl GenesisConfigBuilder {
         self
     }
 
+    pub fn capella_fork_epoch(mut self, capella_fork_epoch: Option<u64>) -> Self {
+        self.capella_fork_epoch = capella_fork_epoch.map(ForkSchedule::Epoch);
+        self
+    }
+
+    pub fn deneb_fork_epoch(mut self, deneb_fork_epoch: Option<u64>) -> Self {
+        self.deneb_fork_epoch = deneb_fork_epoch.map(ForkSchedule::Epoch);
+        self
+    }
+
     pub fn electra_fork_epoch(mut self, electra_fork_epoch: Option<u64>) -> Self {
-        self.electra_fork_epoch = electra_fork_epoch;
+        self.electra_fork_epoch = electra_fork_epoch.map(ForkSchedule::Epoch);
         self
     }
 
     pub fn eof_activation_epoch(mut self, eof_activation_epoch: Option<u64>) -> Self {
-        self.eof_activation_epoch = eof_activation_epoch;
+        self.eof_activation_epoch = eof_activation_epoch.map(ForkSchedule::Epoch);
         self
     }
 
+    pub fn el_clients(mut self, el_clients: Vec<String>) -> Self {
+        self.el_clients = el_clients;
+        self
+    }
+
+    pub fn cl_clients(mut self, cl_clients: Vec<String>) -> Self {
+        self.cl_clients = cl_clients;
+        self
+    }
+
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn consensus(mut self, consensus: ConsensusParams) -> Self {
+        self.consensus = consensus;
+        self
+    }
+
+    pub fn el_premine_count(mut self, el_premine_count: u32) -> Self {
+        self.el_premine_count = Some(el_premine_count);
+        self
+    }
+
+    pub fn el_premine_balance(mut self, el_premine_balance: U256) -> Self {
+        self.el_premine_balance = Some(el_premine_balance);
+        self
+    }
+
+    pub fn el_premine_start_index(mut self, el_premine_start_index: u32) -> Self {
+        self.el_premine_start_index = Some(el_premine_start_index);
+        self
+    }
+
+    /// Premines `DEFAULT_PREMINE_COUNT` accounts with `DEFAULT_PREMINE_BALANCE_ETH` each,
+    /// keeping any count, balance or start index that was already configured.
     pub fn with_default_premine(mut self) -> Self {
-        self.el_premine
-            .insert("m/44'/60'/0'/0/0".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/1".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/2".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/3".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/4".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/5".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/6".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/7".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/8".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/9".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/10".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/11".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/12".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/13".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/14".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/15".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/16".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/17".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/18".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/19".to_string(), "1000000000ETH".to_string());
-        self.el_premine
-            .insert("m/44'/60'/0'/0/20".to_string(), "1000000000ETH".to_string());
+        self.el_premine_count.get_or_insert(DEFAULT_PREMINE_COUNT);
+        self.el_premine_balance
+            .get_or_insert_with(default_premine_balance);
+        self.el_premine_start_index.get_or_insert(0);
         self
     }
 
-    pub fn build(self) -> GenesisConfig {
-        GenesisConfig {
-            preset_base: self.preset_base.expect("preset_base is required"),
+    /// Builds the config, rounding forks scheduled by time up to the next epoch boundary.
+    /// Fails if a fork is scheduled at a time before CL genesis or at an epoch whose start time
+    /// does not fit in a `u64`.
+    pub fn build(self) -> Result<GenesisConfig> {
+        let preset_base = self.preset_base.expect("preset_base is required");
+        let genesis_timestamp = self
+            .genesis_timestamp
+            .expect("genesis_timestamp is required");
+        let genesis_delay = self.genesis_delay.expect("genesis_delay is required");
+        let slot_duration_in_seconds = self
+            .slot_duration_in_seconds
+            .expect("slot_duration_in_seconds is required");
+        let genesis_time = genesis_timestamp + genesis_delay;
+        let seconds_per_epoch = slots_per_epoch(&preset_base) * slot_duration_in_seconds;
+        let epoch = |fork: Fork, schedule: Option<ForkSchedule>| -> Result<Option<u64>> {
+            let Some(schedule) = schedule else {
+                return Ok(None);
+            };
+            let key = fork.config_key();
+            let epoch = schedule
+                .epoch(genesis_time, seconds_per_epoch)
+                .with_context(|| format!("Invalid {}", key))?;
+            if checked_activation_time(
+                genesis_timestamp,
+                genesis_delay,
+                &preset_base,
+                slot_duration_in_seconds,
+                epoch,
+            )
+            .is_none()
+            {
+                bail!("Invalid {}: epoch {} starts after the largest timestamp", key, epoch);
+            }
+            Ok(Some(epoch))
+        };
+        let capella_fork_epoch = epoch(Fork::Shanghai, self.capella_fork_epoch)?;
+        let deneb_fork_epoch = epoch(Fork::Cancun, self.deneb_fork_epoch)?;
+        let electra_fork_epoch = epoch(Fork::Prague, self.electra_fork_epoch)?;
+        let eof_activation_epoch = epoch(Fork::PragueEof, self.eof_activation_epoch)?;
+        Ok(GenesisConfig {
+            preset_base,
             chain_id: self.chain_id.expect("chain_id is required"),
             deposit_contract_address: self
                 .deposit_contract_address
                 .expect("deposit_contract_address is required"),
             mnemonic: self.mnemonic.expect("mnemonic is required"),
             el_premine: self.el_premine,
+            el_premine_count: self.el_premine_count.unwrap_or(0),
+            el_premine_balance: self
+                .el_premine_balance
+                .unwrap_or_else(default_premine_balance),
+            el_premine_start_index: self.el_premine_start_index.unwrap_or(0),
+            el_premine_keystores: self.el_premine_keystores,
+            el_premine_keystore_password: self.el_premine_keystore_password,
             el_premine_addrs: self.el_premine_addrs,
             additional_preloaded_contracts: self.additional_preloaded_contracts,
-            genesis_timestamp: self
-                .genesis_timestamp
-                .expect("genesis_timestamp is required"),
-            genesis_delay: self.genesis_delay.expect("genesis_delay is required"),
+            alloc_conflict_policy: self.alloc_conflict_policy,
+            genesis_timestamp,
+            genesis_delay,
             genesis_gaslimit: self.genesis_gaslimit,
-            slot_duration_in_seconds: self
-                .slot_duration_in_seconds
-                .expect("slot_duration_in_seconds is required"),
-            electra_fork_epoch: self.electra_fork_epoch,
-            eof_activation_epoch: self.eof_activation_epoch,
+            slot_duration_in_seconds,
+            capella_fork_epoch,
+            deneb_fork_epoch,
+            electra_fork_epoch,
+            eof_activation_epoch,
+            el_clients: self.el_clients,
+            cl_clients: self.cl_clients,
+            seed: self.seed,
+            consensus: self.consensus,
+        })
+    }
+}
+
+/// Timestamp at which `activation_epoch` starts, counted from the end of the genesis delay.
+/// Panics if it does not fit in a `u64`, which `GenesisConfigBuilder::build` rules out for the
+/// configured fork epochs.
+pub fn get_activation_epoch(
+    genesis_timestamp: u64,
+    genesis_delay: u64,
+    preset_base: &str,
+    slot_duration_in_seconds: u64,
+    activation_epoch: u64,
+) -> u64 {
+    checked_activation_time(
+        genesis_timestamp,
+        genesis_delay,
+        preset_base,
+        slot_duration_in_seconds,
+        activation_epoch,
+    )
+    .unwrap_or_else(|| panic!("epoch {} starts after the largest timestamp", activation_epoch))
+}
+
+/// Like `get_activation_epoch`, `None` instead of overflowing.
+pub fn checked_activation_time(
+    genesis_timestamp: u64,
+    genesis_delay: u64,
+    preset_base: &str,
+    slot_duration_in_seconds: u64,
+    activation_epoch: u64,
+) -> Option<u64> {
+    activation_epoch
+        .checked_mul(slots_per_epoch(preset_base))?
+        .checked_mul(slot_duration_in_seconds)?
+        .checked_add(genesis_timestamp)?
+        .checked_add(genesis_delay)
+}
+
+pub fn slots_per_epoch(preset_base: &str) -> u64 {
+    if preset_base == "mainnet" {
+        32
+    } else {
+        8
+    }
+}
+
+/// Parses a key with `FromStr`, treating an empty value as unset.
+pub(super) fn parse_value<T>(
+    config_map: &HashMap<String, String>,
+    key: &str,
+    mode: ConfigMode,
+) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    parse_value_with(config_map, key, mode, |value| {
+        value.parse().map_err(|e: T::Err| anyhow::anyhow!("{}", e))
+    })
+}
+
+/// `parse_value` with a parser of its own for values without a fitting `FromStr`.
+pub(super) fn parse_value_with<T>(
+    config_map: &HashMap<String, String>,
+    key: &str,
+    mode: ConfigMode,
+    parse: impl FnOnce(&str) -> Result<T>,
+) -> Result<Option<T>> {
+    let Some(value) = config_map.get(key).filter(|value| !value.trim().is_empty()) else {
+        return Ok(None);
+    };
+    match parse(value.trim()) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            mode.report(format!("invalid value {:?} for {}: {:#}", value, key, e))?;
+            Ok(None)
         }
     }
 }
 
-fn parse_allocation<'a>(str: &'a str) -> Result<HashMap<String, Allocation>> {
-    serde_json::from_str(&str).context("Can't parse string as allocation struct")
+/// When a fork activates, as configured: an epoch, a UTC time or an offset after CL genesis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForkSchedule {
+    Epoch(u64),
+    At(u64),
+    AfterGenesis(u64),
+}
+
+impl ForkSchedule {
+    /// The activation epoch, the first epoch starting at or after a scheduled time.
+    pub fn epoch(&self, genesis_time: u64, seconds_per_epoch: u64) -> Result<u64> {
+        let offset = match *self {
+            ForkSchedule::Epoch(epoch) => return Ok(epoch),
+            ForkSchedule::At(time) => time.checked_sub(genesis_time).with_context(|| {
+                format!(
+                    "{} is before CL genesis at {}",
+                    format_utc(time),
+                    format_utc(genesis_time)
+                )
+            })?,
+            ForkSchedule::AfterGenesis(offset) => offset,
+        };
+        if seconds_per_epoch == 0 {
+            bail!("cannot schedule a fork by time with a slot duration of 0 seconds");
+        }
+        Ok(offset.div_ceil(seconds_per_epoch))
+    }
+
+    /// Parses a fork time, a UTC time or Unix timestamp or an offset after CL genesis such as
+    /// `+2h`. Unlike `from_str`, a bare number is a timestamp, never an epoch.
+    pub fn from_time(value: &str) -> Result<Self> {
+        match value.trim().strip_prefix('+') {
+            Some(offset) => Ok(ForkSchedule::AfterGenesis(parse_duration(offset)?)),
+            None => Ok(ForkSchedule::At(parse_utc_time(value)?)),
+        }
+    }
+}
+
+/// Accepts an epoch such as `10`, a UTC time such as `2024-05-14T14:00:00Z` or an offset after
+/// CL genesis such as `+2h`.
+impl FromStr for ForkSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        match value.parse() {
+            Ok(epoch) if !value.starts_with('+') => Ok(ForkSchedule::Epoch(epoch)),
+            _ => ForkSchedule::from_time(value),
+        }
+    }
+}
+
+/// Parses a fork schedule, mapping `FAR_FUTURE_EPOCH` to `Some(None)` (configured but not
+/// scheduled).
+fn parse_fork(
+    config_map: &HashMap<String, String>,
+    key: &str,
+    mode: ConfigMode,
+) -> Result<Option<Option<ForkSchedule>>> {
+    let fork = parse_value(config_map, key, mode)?;
+    Ok(fork.map(|fork| Some(fork).filter(|fork| *fork != ForkSchedule::Epoch(FAR_FUTURE_EPOCH))))
+}
+
+/// Parses a comma separated client list such as `geth, besu`.
+fn parse_clients(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|client| client.trim().to_lowercase())
+        .filter(|client| !client.is_empty())
+        .collect()
+}
+
+fn parse_allocation(str: &str) -> Result<Alloc> {
+    let entries = serde_json::from_str(str).context("Can't parse string as allocation struct")?;
+    parse_alloc(entries)
+}
+
+/// Writes an allocation in the form `parse_allocation` reads, sorted by address and keeping
+/// the secret keys the genesis files leave out.
+fn allocation_to_string(alloc: &Alloc) -> Result<String> {
+    let mut entries = BTreeMap::new();
+    for (address, allocation) in alloc {
+        let mut entry = serde_json::to_value(allocation)?;
+        if let (Value::Object(entry), Some(secret_key)) = (&mut entry, &allocation.secret_key) {
+            entry.insert("secretKey".to_string(), Value::String(secret_key.clone()));
+        }
+        entries.insert(to_checksum(address, None), entry);
+    }
+    Ok(serde_json::to_string(&entries)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ethers::types::Bytes;
 
     #[test]
     fn test_allocation_parsing() {
@@ -239,18 +691,208 @@ mod tests {
         assert!(result.is_ok());
         let map = result.unwrap();
 
-        let address = "0x123463a4B065722E99115D6c222f267d9cABb524";
-        let balance = "1ETH";
-        let code = "0x123465";
+        let address: Address = "0x123463a4B065722E99115D6c222f267d9cABb524".parse().unwrap();
+        let balance = U256::exp10(18);
+        let code: Bytes = "0x123465".parse().unwrap();
         let nonce = 0;
         let secret_key = "0x";
 
-        assert_eq!(map[address].balance, balance);
-        assert_eq!(map[address].code, Some(code.into()));
-        assert_eq!(map[address].nonce, Some(nonce));
-        assert_eq!(map[address].secret_key, Some(secret_key.into()));
+        assert_eq!(map[&address].balance, balance);
+        assert_eq!(map[&address].code, Some(code));
+        assert_eq!(map[&address].nonce, Some(nonce));
+        assert_eq!(map[&address].secret_key, Some(secret_key.into()));
+
+        let storage = map[&address].storage.as_ref().unwrap();
+        assert!(storage.is_empty());
+    }
+
+    #[test]
+    fn test_default_premine_keeps_configured_values() {
+        let mut context = HashMap::new();
+        context.insert("EL_PREMINE_COUNT".to_string(), "100000".to_string());
+        context.insert("EL_PREMINE_BALANCE".to_string(), "5ETH".to_string());
+
+        let builder = GenesisConfigBuilder::new()
+            .from_hashmap(context)
+            .unwrap()
+            .with_default_premine();
+
+        assert_eq!(builder.el_premine_count, Some(100000));
+        assert_eq!(builder.el_premine_balance, Some(U256::from(5) * U256::exp10(18)));
+        assert_eq!(builder.el_premine_start_index, Some(0));
+
+        let builder = GenesisConfigBuilder::new().with_default_premine();
+        assert_eq!(builder.el_premine_count, Some(DEFAULT_PREMINE_COUNT));
+        assert_eq!(builder.el_premine_balance, Some(default_premine_balance()));
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_malformed_numbers() {
+        let mut context = HashMap::new();
+        context.insert("GENESIS_DELAY".to_string(), "sixty".to_string());
+
+        let result = GenesisConfigBuilder::new()
+            .config_mode(ConfigMode::Strict)
+            .from_hashmap(context.clone());
+        assert!(result.is_err());
+
+        let builder = GenesisConfigBuilder::new()
+            .config_mode(ConfigMode::Warn)
+            .from_hashmap(context)
+            .unwrap();
+        assert_eq!(builder.genesis_delay, None);
+    }
+
+    #[test]
+    fn test_config_map_round_trips_explicit_premine_paths() {
+        let config = GenesisConfigBuilder::new()
+            .preset_base("minimal".to_string())
+            .chain_id(1337)
+            .deposit_contract_address("0x4242424242424242424242424242424242424242".to_string())
+            .mnemonic("test test test test test test test test test test test junk".to_string())
+            .el_premine("m/44'/60'/0'/0/1".to_string(), "5ETH".to_string())
+            .el_premine("m/44'/60'/0'/0/7".to_string(), "1ETH".to_string())
+            .el_premine_count(3)
+            .genesis_timestamp(1_715_688_000)
+            .genesis_delay(60)
+            .slot_duration_in_seconds(6)
+            .capella_fork_epoch(Some(0))
+            .electra_fork_epoch(Some(4))
+            .with_default_premine()
+            .build()
+            .unwrap();
+        let config_map = config.to_config_map().unwrap();
+
+        let rebuilt = GenesisConfigBuilder::new()
+            .config_mode(ConfigMode::Strict)
+            .from_hashmap(config_map.clone().into_iter().collect())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(rebuilt.el_premine, config.el_premine);
+        assert_eq!(rebuilt.to_config_map().unwrap(), config_map);
+    }
+
+    #[test]
+    fn test_warn_mode_skips_malformed_premine_values() {
+        let mut context = HashMap::new();
+        context.insert("EL_PREMINE_COUNT".to_string(), "many".to_string());
+        context.insert("EL_PREMINE_BALANCE".to_string(), "5 coins".to_string());
+        context.insert("EL_PREMINE_START_INDEX".to_string(), "-1".to_string());
+        context.insert("EL_PREMINE_KEYSTORES".to_string(), "yes".to_string());
+
+        for key in ["EL_PREMINE_COUNT", "EL_PREMINE_BALANCE"] {
+            let context = HashMap::from([(key.to_string(), context[key].clone())]);
+            let error = GenesisConfigBuilder::new()
+                .config_mode(ConfigMode::Strict)
+                .from_hashmap(context)
+                .err()
+                .unwrap();
+            assert!(error.to_string().contains(key), "{}", error);
+        }
+
+        let builder = GenesisConfigBuilder::new()
+            .config_mode(ConfigMode::Warn)
+            .from_hashmap(context)
+            .unwrap();
+        assert_eq!(builder.el_premine_count, None);
+        assert_eq!(builder.el_premine_balance, None);
+        assert_eq!(builder.el_premine_start_index, None);
+        assert!(!builder.el_premine_keystores);
+    }
+
+    #[test]
+    fn test_config_mode_key_selects_mode() {
+        let mut context = HashMap::new();
+        context.insert("CONFIG_MODE".to_string(), "strict".to_string());
+        context.insert("ELECTRA_FORK_EPOC".to_string(), "1".to_string());
+
+        let error = GenesisConfigBuilder::new()
+            .from_hashmap(context)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("did you mean ELECTRA_FORK_EPOCH?"));
+    }
+
+    #[test]
+    fn test_parses_client_selection() {
+        let mut context = HashMap::new();
+        context.insert("EL_CLIENTS".to_string(), "Geth, besu,".to_string());
+        context.insert("CL_CLIENTS".to_string(), "lighthouse".to_string());
+
+        let builder = GenesisConfigBuilder::new().from_hashmap(context.clone()).unwrap();
+        assert_eq!(builder.el_clients, ["geth", "besu"]);
+        assert_eq!(builder.cl_clients, ["lighthouse"]);
+
+        context.insert("CL_CLIENTS".to_string(), "lighthouse,tekku".to_string());
+        let error = GenesisConfigBuilder::new().from_hashmap(context).err().unwrap();
+        assert!(error.to_string().starts_with("unknown CL client tekku"));
+    }
+
+    #[test]
+    fn test_rounds_fork_times_up_to_epoch_boundaries() {
+        let mut context = HashMap::new();
+        context.insert("CAPELLA_FORK_EPOCH".to_string(), "0".to_string());
+        context.insert("DENEB_FORK_EPOCH".to_string(), "+2h".to_string());
+        context.insert("ELECTRA_FORK_EPOCH".to_string(), "2024-05-14T14:00:00Z".to_string());
+        context.insert("EOF_ACTIVATION_EPOCH".to_string(), FAR_FUTURE_EPOCH.to_string());
+
+        let config = GenesisConfigBuilder::new()
+            .from_hashmap(context)
+            .unwrap()
+            .preset_base("mainnet".to_string())
+            .chain_id(1337)
+            .deposit_contract_address("0x4242424242424242424242424242424242424242".to_string())
+            .mnemonic(String::new())
+            .genesis_timestamp(1_715_688_000)
+            .genesis_delay(60)
+            .slot_duration_in_seconds(12)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.capella_fork_epoch, Some(0));
+        // 7200s is 18.75 epochs of 384s.
+        assert_eq!(config.deneb_fork_epoch, Some(19));
+        // 14:00 is 7140s after CL genesis at 12:01.
+        assert_eq!(config.electra_fork_epoch, Some(19));
+        assert_eq!(config.eof_activation_epoch, None);
+        assert_eq!(config.activation_time(19), 1_715_695_356);
+
+        let mut context = HashMap::new();
+        context.insert("DENEB_FORK_EPOCH".to_string(), "+2 hours".to_string());
+        let result = GenesisConfigBuilder::new()
+            .config_mode(ConfigMode::Strict)
+            .from_hashmap(context);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_rejects_fork_times_before_genesis() {
+        let builder = |electra: &str, slot_duration_in_seconds: u64| {
+            let mut context = HashMap::new();
+            context.insert("ELECTRA_FORK_EPOCH".to_string(), electra.to_string());
+            GenesisConfigBuilder::new()
+                .from_hashmap(context)
+                .unwrap()
+                .preset_base("mainnet".to_string())
+                .chain_id(1337)
+                .deposit_contract_address("0x4242424242424242424242424242424242424242".to_string())
+                .mnemonic(String::new())
+                .genesis_timestamp(1_715_688_000)
+                .genesis_delay(60)
+                .slot_duration_in_seconds(slot_duration_in_seconds)
+        };
 
-        // let storage = &map[address].storage;
-        // assert!(storage.unwrap().is_empty());
+        let error = builder("2024-05-14T12:00:00Z", 12).build().err().unwrap();
+        assert_eq!(error.to_string(), "Invalid ELECTRA_FORK_EPOCH");
+        assert!(format!("{:#}", error).contains("is before CL genesis at 2024-05-14 12:01:00 UTC"));
+        assert_eq!(
+            builder("2024-05-14T12:01:00Z", 12).build().unwrap().electra_fork_epoch,
+            Some(0)
+        );
+        assert!(builder("+2h", 0).build().is_err());
+        assert!(builder("18446744073709551614", 12).build().is_err());
+        assert_eq!(builder("3", 0).build().unwrap().electra_fork_epoch, Some(3));
     }
 }
@@ -0,0 +1,333 @@
+use anyhow::{Context, Result};
+use ethers::{
+    types::{Address, Bytes, H256, U256},
+    utils::{keccak256, rlp::RlpStream},
+};
+
+use sha2::{Digest, Sha256};
+
+use super::{
+    allocation::{Alloc, Allocation},
+    network_genesis::NetworkGenesis,
+};
+
+/// Base fee geth puts in the genesis header when the genesis does not set one.
+const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+
+/// Hash of the genesis block a client builds from `genesis`, following geth's rules for which
+/// header fields exist when London, Shanghai, Cancun and Prague are active at genesis.
+pub fn genesis_block_hash(genesis: &NetworkGenesis) -> Result<H256> {
+    genesis_header_hash(genesis, state_root(&genesis.alloc))
+}
+
+/// Hash of the genesis header of `genesis` with the given state root.
+fn genesis_header_hash(genesis: &NetworkGenesis, state_root: H256) -> Result<H256> {
+    let config = &genesis.config;
+    let header = &genesis.header;
+    let timestamp = parse_u64(&header.timestamp).context("Invalid genesis timestamp")?;
+    let base_fee = match header.base_fee_per_gas.as_str() {
+        "" => U256::from(INITIAL_BASE_FEE),
+        base_fee => parse_u256(base_fee).context("Invalid genesis baseFeePerGas")?,
+    };
+
+    let mut rlp = RlpStream::new();
+    rlp.begin_unbounded_list();
+    rlp.append(&parse_h256(&header.parent_hash).context("Invalid genesis parentHash")?);
+    rlp.append(&H256::from(keccak256([0xc0])));
+    rlp.append(
+        &header
+            .coinbase
+            .parse::<Address>()
+            .context("Invalid genesis coinbase")?,
+    );
+    rlp.append(&state_root);
+    rlp.append(&empty_trie_root());
+    rlp.append(&empty_trie_root());
+    rlp.append(&vec![0u8; 256]);
+    rlp.append(&parse_u256(&header.difficulty).context("Invalid genesis difficulty")?);
+    rlp.append(&0u64);
+    rlp.append(&parse_u64(&header.gas_limit).context("Invalid genesis gasLimit")?);
+    rlp.append(&0u64);
+    rlp.append(&timestamp);
+    rlp.append(
+        &parse_bytes(&header.extra_data)
+            .context("Invalid genesis extraData")?
+            .to_vec(),
+    );
+    rlp.append(&parse_h256(&header.mixhash).context("Invalid genesis mixhash")?);
+    let nonce = parse_u64(&header.nonce).context("Invalid genesis nonce")?;
+    rlp.append(&nonce.to_be_bytes().to_vec());
+    if config.london_block == 0 {
+        rlp.append(&base_fee);
+    }
+    if config.shanghai_time <= timestamp {
+        rlp.append(&empty_trie_root());
+    }
+    if config.cancun_time <= timestamp {
+        rlp.append(&0u64);
+        rlp.append(&0u64);
+        rlp.append(&H256::zero());
+    }
+    if config
+        .prague_time
+        .is_some_and(|prague_time| prague_time <= timestamp)
+    {
+        // EIP-7685 commitment to an empty request list.
+        rlp.append(&H256::from_slice(&Sha256::digest([])));
+    }
+    rlp.finalize_unbounded_list();
+    Ok(H256::from(keccak256(rlp.out())))
+}
+
+/// Root of the state trie holding the accounts of `alloc`.
+pub fn state_root(alloc: &Alloc) -> H256 {
+    let mut accounts = Vec::new();
+    for (address, allocation) in alloc {
+        accounts.push((keccak256(address), account_rlp(allocation)));
+    }
+    trie_root(accounts)
+}
+
+fn account_rlp(allocation: &Allocation) -> Vec<u8> {
+    let code = allocation.code.clone().unwrap_or_default();
+    let mut slots = Vec::new();
+    for (slot, value) in allocation.storage.iter().flatten() {
+        if !value.is_zero() {
+            let mut value_rlp = RlpStream::new();
+            value_rlp.append(&U256::from_big_endian(value.as_bytes()));
+            slots.push((keccak256(slot), value_rlp.out().to_vec()));
+        }
+    }
+
+    let mut account = RlpStream::new_list(4);
+    account.append(&allocation.nonce.unwrap_or(0));
+    account.append(&allocation.balance);
+    account.append(&trie_root(slots));
+    account.append(&H256::from(keccak256(&code)));
+    account.out().to_vec()
+}
+
+fn empty_trie_root() -> H256 {
+    H256::from(keccak256([0x80]))
+}
+
+/// Root of the Merkle Patricia trie holding `entries`.
+pub fn trie_root<K: AsRef<[u8]>>(entries: Vec<(K, Vec<u8>)>) -> H256 {
+    if entries.is_empty() {
+        return empty_trie_root();
+    }
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = entries
+        .into_iter()
+        .map(|(key, value)| (nibbles(key.as_ref()), value))
+        .collect();
+    entries.sort();
+    H256::from(keccak256(encode_node(&entries, 0)))
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+fn encode_node(entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+    if let [(key, value)] = entries {
+        let mut leaf = RlpStream::new_list(2);
+        leaf.append(&hex_prefix(&key[depth..], true));
+        leaf.append(value);
+        return leaf.out().to_vec();
+    }
+
+    let first = &entries[0].0;
+    let last = &entries[entries.len() - 1].0;
+    let shared = first[depth..]
+        .iter()
+        .zip(&last[depth..])
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shared > 0 {
+        let mut extension = RlpStream::new_list(2);
+        extension.append(&hex_prefix(&first[depth..depth + shared], false));
+        append_child(&mut extension, &encode_node(entries, depth + shared));
+        return extension.out().to_vec();
+    }
+
+    let mut branch = RlpStream::new_list(17);
+    for nibble in 0..16u8 {
+        let children: Vec<_> = entries
+            .iter()
+            .filter(|(key, _)| key.get(depth) == Some(&nibble))
+            .cloned()
+            .collect();
+        if children.is_empty() {
+            branch.append_empty_data();
+        } else {
+            append_child(&mut branch, &encode_node(&children, depth + 1));
+        }
+    }
+    match entries.iter().find(|(key, _)| key.len() == depth) {
+        Some((_, value)) => branch.append(value),
+        None => branch.append_empty_data(),
+    };
+    branch.out().to_vec()
+}
+
+/// Nodes shorter than a hash are embedded in their parent, longer ones referenced by hash.
+fn append_child(stream: &mut RlpStream, child: &[u8]) {
+    if child.len() < 32 {
+        stream.append_raw(child, 1);
+    } else {
+        stream.append(&H256::from(keccak256(child)));
+    }
+}
+
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 } + nibbles.len() as u8 % 2;
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push(flag << 4 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag << 4);
+        nibbles
+    };
+    encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    encoded
+}
+
+/// Parses a genesis quantity written as `0x` hex or decimal.
+pub(crate) fn parse_u256(value: &str) -> Result<U256> {
+    match value.strip_prefix("0x") {
+        Some(hex) => Ok(U256::from_str_radix(hex, 16)?),
+        None => Ok(U256::from_dec_str(value)?),
+    }
+}
+
+pub(crate) fn parse_u64(value: &str) -> Result<u64> {
+    let number = parse_u256(value)?;
+    anyhow::ensure!(
+        number <= U256::from(u64::MAX),
+        "{} does not fit 64 bits",
+        value
+    );
+    Ok(number.as_u64())
+}
+
+pub(crate) fn parse_h256(value: &str) -> Result<H256> {
+    let bytes = parse_bytes(value)?;
+    anyhow::ensure!(bytes.len() <= 32, "{} is longer than 32 bytes", value);
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(H256::from(word))
+}
+
+pub(crate) fn parse_bytes(value: &str) -> Result<Bytes> {
+    if value.is_empty() {
+        return Ok(Bytes::new());
+    }
+    Ok(value.parse()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::{config::Config, network_genesis::GenesisHeader};
+
+    const ZERO_HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+    fn network_genesis(
+        london_block: u32,
+        shanghai_time: u64,
+        header: GenesisHeader,
+    ) -> NetworkGenesis {
+        NetworkGenesis {
+            config: Config {
+                london_block,
+                shanghai_time,
+                cancun_time: u64::MAX,
+                ..Config::new(1, String::new())
+            },
+            alloc: Alloc::default(),
+            header,
+        }
+    }
+
+    fn header(difficulty: &str, extra_data: &str, gas_limit: &str, nonce: &str) -> GenesisHeader {
+        GenesisHeader {
+            coinbase: "0x0000000000000000000000000000000000000000".to_string(),
+            base_fee_per_gas: String::new(),
+            difficulty: difficulty.to_string(),
+            extra_data: extra_data.to_string(),
+            gas_limit: gas_limit.to_string(),
+            nonce: nonce.to_string(),
+            mixhash: ZERO_HASH.to_string(),
+            parent_hash: ZERO_HASH.to_string(),
+            timestamp: "0x0".to_string(),
+        }
+    }
+
+    /// The published genesis blocks of mainnet and Holesky, hashed from their headers and
+    /// state roots since their allocs are too large to inline.
+    #[test]
+    fn test_header_hash_matches_published_genesis_blocks() {
+        let mainnet = network_genesis(
+            12_965_000,
+            1_681_338_455,
+            header(
+                "0x400000000",
+                "0x11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa",
+                "0x1388",
+                "0x42",
+            ),
+        );
+        let state_root = "0xd7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544";
+        assert_eq!(
+            genesis_header_hash(&mainnet, state_root.parse().unwrap()).unwrap(),
+            "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                .parse()
+                .unwrap()
+        );
+
+        let holesky = network_genesis(
+            0,
+            1_696_000_704,
+            GenesisHeader {
+                base_fee_per_gas: "0x3b9aca00".to_string(),
+                timestamp: "0x65156994".to_string(),
+                ..header("0x1", "0x", "0x17d7840", "0x1234")
+            },
+        );
+        let state_root = "0x69d8c9d72f6fa4ad42d4702b433707212f90db395eb54dc20bc85de253788783";
+        assert_eq!(
+            genesis_header_hash(&holesky, state_root.parse().unwrap()).unwrap(),
+            "0xb5f7f912443c940f21fd611f12828d75b534364ed9e95ca4e307729a4661bde4"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_trie_root_matches_reference_vectors() {
+        assert_eq!(
+            trie_root::<Vec<u8>>(vec![]),
+            "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+                .parse()
+                .unwrap()
+        );
+
+        let entries = [
+            ("doe", "reindeer"),
+            ("dog", "puppy"),
+            ("dogglesworth", "cat"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.as_bytes(), value.as_bytes().to_vec()))
+        .collect();
+        assert_eq!(
+            trie_root(entries),
+            "0x8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"
+                .parse()
+                .unwrap()
+        );
+    }
+}
@@ -1,6 +1,17 @@
+pub mod accounts;
+pub mod allocation;
 pub mod besu_generation;
 pub mod chainspec_generation;
 pub mod geth_generation;
+pub mod genesis_builder;
+pub mod genesis_hash;
 pub mod genesis_config;
+pub mod nethermind_chainspec;
+pub mod network_genesis;
 pub mod serializabe_to_file;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod config_keys;
+pub mod format_registry;
+pub mod consensus_params;
+#[cfg(test)]
+pub(crate) mod fixtures;
\ No newline at end of file
@@ -0,0 +1,481 @@
+use anyhow::{bail, Context, Result};
+use ethers::types::{Bytes, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::{
+    allocation::{parse_address, parse_balance, parse_storage_word, Alloc, Allocation},
+    config::Config,
+    genesis_hash::parse_u64,
+    network_genesis::{GenesisHeader, NetworkGenesis},
+    serializabe_to_file::SerializableToFile,
+};
+
+/// Transitions of the Shanghai fork, each EIP activated by its own timestamp.
+const SHANGHAI: &[&str] = &[
+    "eip3651TransitionTimestamp",
+    "eip3855TransitionTimestamp",
+    "eip3860TransitionTimestamp",
+    "eip4895TransitionTimestamp",
+];
+const CANCUN: &[&str] = &[
+    "eip1153TransitionTimestamp",
+    "eip4788TransitionTimestamp",
+    "eip4844TransitionTimestamp",
+    "eip5656TransitionTimestamp",
+    "eip6780TransitionTimestamp",
+];
+const PRAGUE: &[&str] = &[
+    "eip2537TransitionTimestamp",
+    "eip2935TransitionTimestamp",
+    "eip6110TransitionTimestamp",
+    "eip7002TransitionTimestamp",
+    "eip7251TransitionTimestamp",
+    "eip7623TransitionTimestamp",
+    "eip7702TransitionTimestamp",
+];
+const PRAGUE_EOF: &[&str] = &["eip7692TransitionTimestamp"];
+
+/// Chain limits Nethermind reads from `params`, written with their mainnet values.
+const LIMITS: &[(&str, &str)] = &[
+    ("gasLimitBoundDivisor", "0x400"),
+    ("maximumExtraDataSize", "0x20"),
+    ("minGasLimit", "0x1388"),
+    ("maxCodeSize", "0x6000"),
+    ("maxCodeSizeTransition", "0x0"),
+];
+
+/// A chainspec in the layout Nethermind reads, where forks are scheduled per EIP in `params`
+/// instead of per fork in `config` as in the geth-style formats. Only used by `convert`: the
+/// `chainspec.json` written by the generator keeps the geth-style layout of its template.
+#[derive(Serialize, Deserialize)]
+pub struct NethermindChainspec {
+    #[serde(default)]
+    pub name: String,
+    pub engine: Engine,
+    pub params: BTreeMap<String, Value>,
+    pub genesis: ChainspecGenesis,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, ChainspecAccount>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Engine {
+    #[serde(rename = "Ethash")]
+    pub ethash: EthashEngine,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct EthashEngine {
+    #[serde(default)]
+    pub params: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainspecGenesis {
+    pub seal: Seal,
+    pub difficulty: String,
+    pub author: String,
+    pub timestamp: String,
+    pub parent_hash: String,
+    pub extra_data: String,
+    pub gas_limit: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Seal {
+    pub ethereum: EthereumSeal,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthereumSeal {
+    pub nonce: String,
+    pub mix_hash: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct ChainspecAccount {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+}
+
+impl NethermindChainspec {
+    pub const NAME: &'static str = "nethermind";
+    /// The client reading this format, selected with `convert --from` and `--to`.
+    pub const CLIENT: &'static str = "nethermind";
+}
+
+impl SerializableToFile for NethermindChainspec {}
+
+/// The block-activated forks of `config`, each with the transitions Nethermind enables it
+/// with. Homestead is scheduled in the Ethash engine instead.
+fn block_forks(config: &mut Config) -> [(&mut u32, &'static [&'static str]); 10] {
+    [
+        (&mut config.eip150_block, &["eip150Transition"]),
+        (&mut config.eip155_block, &["eip155Transition"]),
+        (
+            &mut config.eip158_block,
+            &[
+                "eip160Transition",
+                "eip161abcTransition",
+                "eip161dTransition",
+            ],
+        ),
+        (
+            &mut config.byzantium_block,
+            &[
+                "eip140Transition",
+                "eip211Transition",
+                "eip214Transition",
+                "eip658Transition",
+            ],
+        ),
+        (
+            &mut config.constantinople_block,
+            &[
+                "eip145Transition",
+                "eip1014Transition",
+                "eip1052Transition",
+                "eip1283Transition",
+            ],
+        ),
+        (&mut config.petersburg_block, &["eip1283DisableTransition"]),
+        (
+            &mut config.istanbul_block,
+            &[
+                "eip152Transition",
+                "eip1108Transition",
+                "eip1344Transition",
+                "eip1884Transition",
+                "eip2028Transition",
+                "eip2200Transition",
+            ],
+        ),
+        (
+            &mut config.berlin_block,
+            &[
+                "eip2565Transition",
+                "eip2929Transition",
+                "eip2930Transition",
+            ],
+        ),
+        (
+            &mut config.london_block,
+            &[
+                "eip1559Transition",
+                "eip3198Transition",
+                "eip3529Transition",
+                "eip3541Transition",
+            ],
+        ),
+        (&mut config.pre_merge_fork_block, &["mergeForkIdTransition"]),
+    ]
+}
+
+fn quantity(value: u64) -> Value {
+    Value::String(format!("{:#x}", value))
+}
+
+/// Reads a quantity Nethermind accepts as `0x` hex, decimal or a JSON number.
+fn parse_quantity(value: &Value) -> Result<u64> {
+    match value {
+        Value::String(text) => parse_u64(text),
+        Value::Number(number) => number
+            .as_u64()
+            .with_context(|| format!("{} is not a 64 bit quantity", number)),
+        _ => bail!("{} is not a quantity", value),
+    }
+}
+
+fn read_param(params: &BTreeMap<String, Value>, key: &str) -> Result<Option<u64>> {
+    params
+        .get(key)
+        .map(|value| parse_quantity(value).with_context(|| format!("Invalid {}", key)))
+        .transpose()
+}
+
+/// The activation of the fork enabled by `transitions`, `None` if none of them is set. The
+/// shared model schedules whole forks, so every transition must be set to the same value.
+fn read_fork(params: &BTreeMap<String, Value>, transitions: &[&str]) -> Result<Option<u64>> {
+    let activation = read_param(params, transitions[0])?;
+    for transition in &transitions[1..] {
+        if read_param(params, transition)? != activation {
+            bail!(
+                "{} and {} differ, forks activated EIP by EIP cannot be converted",
+                transitions[0],
+                transition
+            );
+        }
+    }
+    Ok(activation)
+}
+
+impl TryFrom<NetworkGenesis> for NethermindChainspec {
+    type Error = anyhow::Error;
+
+    fn try_from(genesis: NetworkGenesis) -> Result<Self> {
+        let mut config = genesis.config;
+        let header = genesis.header;
+        let mut params = BTreeMap::new();
+        let chain_id = quantity(config.chain_id.into());
+        params.insert("chainID".to_string(), chain_id.clone());
+        params.insert("networkID".to_string(), chain_id);
+        params.insert(
+            "depositContractAddress".to_string(),
+            Value::String(config.deposit_contract_address.clone()),
+        );
+        params.insert(
+            "terminalTotalDifficulty".to_string(),
+            quantity(config.terminal_total_difficulty),
+        );
+        for (key, value) in LIMITS {
+            params.insert(key.to_string(), Value::String(value.to_string()));
+        }
+        let time_forks = [
+            (Some(config.shanghai_time), SHANGHAI),
+            (Some(config.cancun_time), CANCUN),
+            (config.prague_time, PRAGUE),
+            (config.prague_eoftime, PRAGUE_EOF),
+        ];
+        for (time, transitions) in time_forks {
+            if let Some(time) = time {
+                for transition in transitions {
+                    params.insert(transition.to_string(), quantity(time));
+                }
+            }
+        }
+        for (block, transitions) in block_forks(&mut config) {
+            for transition in transitions {
+                params.insert(transition.to_string(), quantity((*block).into()));
+            }
+        }
+
+        let mut ethash = EthashEngine::default();
+        for (key, value) in config.ethash {
+            ethash.params.insert(key, Value::String(value));
+        }
+        ethash.params.insert(
+            "homesteadTransition".to_string(),
+            quantity(config.homestead_block.into()),
+        );
+
+        let accounts = genesis
+            .alloc
+            .into_iter()
+            .map(|(address, allocation)| {
+                let account = ChainspecAccount {
+                    balance: Some(format!("{:#x}", allocation.balance)),
+                    nonce: allocation.nonce.map(|nonce| format!("{:#x}", nonce)),
+                    code: allocation.code,
+                    storage: allocation.storage.map(|storage| {
+                        storage
+                            .into_iter()
+                            .map(|(key, value)| (format!("{:?}", key), format!("{:?}", value)))
+                            .collect()
+                    }),
+                };
+                (format!("{:?}", address), account)
+            })
+            .collect();
+
+        let timestamp = parse_u64(&header.timestamp)
+            .with_context(|| format!("Invalid genesis timestamp {}", header.timestamp))?;
+        Ok(NethermindChainspec {
+            name: "testnet".to_string(),
+            engine: Engine { ethash },
+            params,
+            genesis: ChainspecGenesis {
+                seal: Seal {
+                    ethereum: EthereumSeal {
+                        nonce: header.nonce,
+                        mix_hash: header.mixhash,
+                    },
+                },
+                difficulty: header.difficulty,
+                author: header.coinbase,
+                timestamp: format!("{:#x}", timestamp),
+                parent_hash: header.parent_hash,
+                extra_data: header.extra_data,
+                gas_limit: header.gas_limit,
+                base_fee_per_gas: Some(header.base_fee_per_gas)
+                    .filter(|base_fee| !base_fee.is_empty()),
+            },
+            accounts,
+        })
+    }
+}
+
+impl TryFrom<NethermindChainspec> for NetworkGenesis {
+    type Error = anyhow::Error;
+
+    fn try_from(chainspec: NethermindChainspec) -> Result<Self> {
+        let params = &chainspec.params;
+        let chain_id = read_param(params, "chainID")?.context("chainspec has no chainID")?;
+        let deposit_contract_address = match params.get("depositContractAddress") {
+            Some(Value::String(address)) => address.clone(),
+            Some(address) => bail!("Invalid depositContractAddress {}", address),
+            None => String::new(),
+        };
+        let mut config = Config::new(
+            u32::try_from(chain_id).context("chainID does not fit 32 bits")?,
+            deposit_contract_address,
+        );
+        config.terminal_total_difficulty =
+            read_param(params, "terminalTotalDifficulty")?.unwrap_or_default();
+
+        // The shared model schedules shanghai and cancun in every genesis.
+        config.shanghai_time = read_fork(params, SHANGHAI)?
+            .with_context(|| format!("chainspec does not schedule shanghai ({})", SHANGHAI[0]))?;
+        config.cancun_time = read_fork(params, CANCUN)?
+            .with_context(|| format!("chainspec does not schedule cancun ({})", CANCUN[0]))?;
+        config.prague_time = read_fork(params, PRAGUE)?;
+        config.prague_eoftime = read_fork(params, PRAGUE_EOF)?;
+        for (block, transitions) in block_forks(&mut config) {
+            if let Some(activation) = read_fork(params, transitions)? {
+                *block = u32::try_from(activation)
+                    .with_context(|| format!("{} does not fit 32 bits", transitions[0]))?;
+            }
+        }
+
+        let mut ethash_params = chainspec.engine.ethash.params;
+        if let Some(homestead) = ethash_params.remove("homesteadTransition") {
+            config.homestead_block = u32::try_from(parse_quantity(&homestead)?)
+                .context("homesteadTransition does not fit 32 bits")?;
+        }
+        for (key, value) in ethash_params {
+            if let Value::String(value) = value {
+                config.ethash.insert(key, value);
+            }
+        }
+
+        let mut alloc = Alloc::new();
+        for (key, account) in chainspec.accounts {
+            let address = parse_address(&key)?;
+            let allocation = Allocation {
+                balance: account
+                    .balance
+                    .as_deref()
+                    .map(parse_balance)
+                    .transpose()?
+                    .unwrap_or(U256::zero()),
+                code: account.code,
+                storage: account
+                    .storage
+                    .map(|storage| {
+                        storage
+                            .iter()
+                            .map(|(key, value)| {
+                                Ok((parse_storage_word(key)?, parse_storage_word(value)?))
+                            })
+                            .collect::<Result<BTreeMap<_, _>>>()
+                    })
+                    .transpose()?,
+                nonce: account.nonce.as_deref().map(parse_u64).transpose()?,
+                secret_key: None,
+            };
+            alloc.insert(address, allocation);
+        }
+
+        let genesis = chainspec.genesis;
+        Ok(NetworkGenesis {
+            config,
+            alloc,
+            header: GenesisHeader {
+                coinbase: genesis.author,
+                base_fee_per_gas: genesis.base_fee_per_gas.unwrap_or_default(),
+                difficulty: genesis.difficulty,
+                extra_data: genesis.extra_data,
+                gas_limit: genesis.gas_limit,
+                nonce: genesis.seal.ethereum.nonce,
+                mixhash: genesis.seal.ethereum.mix_hash,
+                parent_hash: genesis.parent_hash,
+                timestamp: genesis.timestamp,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, H256};
+
+    const CHAINSPEC: &str = include_str!("../../tests/fixtures/nethermind/chainspec.json");
+
+    #[test]
+    fn test_round_trips_known_chainspec() {
+        let network: NetworkGenesis = serde_json::from_str::<NethermindChainspec>(CHAINSPEC)
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert_eq!(network.config.chain_id, 3151908);
+        assert_eq!(network.config.london_block, 0);
+        assert_eq!(network.config.shanghai_time, 0x6712c8a0);
+        assert_eq!(network.config.cancun_time, 0x6712c8a0);
+        assert_eq!(network.config.prague_time, Some(0x6712d4c0));
+        assert_eq!(network.config.prague_eoftime, None);
+        assert_eq!(network.header.gas_limit, "0x2255100");
+        let deposit_contract: Address = "0x4242424242424242424242424242424242424242"
+            .parse()
+            .unwrap();
+        let deposit_contract = &network.alloc[&deposit_contract];
+        assert_eq!(deposit_contract.nonce, Some(1));
+        assert_eq!(
+            deposit_contract.storage.as_ref().unwrap()[&H256::from_low_u64_be(0x22)],
+            "0xf5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b"
+                .parse()
+                .unwrap()
+        );
+
+        // Everything but the peers and the precompile definitions is written back unchanged.
+        let written =
+            serde_json::to_value(NethermindChainspec::try_from(network).unwrap()).unwrap();
+        let mut expected: Value = serde_json::from_str(CHAINSPEC).unwrap();
+        expected.as_object_mut().unwrap().remove("nodes");
+        expected["accounts"]["0x0000000000000000000000000000000000000001"]
+            .as_object_mut()
+            .unwrap()
+            .remove("builtin");
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn test_rejects_forks_it_cannot_express() {
+        let read = |remove: &[&str]| {
+            let mut chainspec: Value = serde_json::from_str(CHAINSPEC).unwrap();
+            for transition in remove {
+                chainspec["params"]
+                    .as_object_mut()
+                    .unwrap()
+                    .remove(*transition);
+            }
+            let chainspec: NethermindChainspec = serde_json::from_value(chainspec).unwrap();
+            NetworkGenesis::try_from(chainspec)
+                .err()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            read(&["eip4844TransitionTimestamp"]),
+            "eip1153TransitionTimestamp and eip4844TransitionTimestamp differ, forks activated \
+             EIP by EIP cannot be converted"
+        );
+        assert_eq!(
+            read(CANCUN),
+            "chainspec does not schedule cancun (eip1153TransitionTimestamp)"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use super::{allocation::Alloc, config::Config};
+
+/// Format-independent content of an EL genesis, which every genesis format converts to and
+/// from. Translating through it keeps a conversion between any two formats lossless for every
+/// field the target can express.
+pub struct NetworkGenesis {
+    pub config: Config,
+    pub alloc: Alloc,
+    pub header: GenesisHeader,
+}
+
+/// Fields of the genesis block header, as written in genesis files.
+#[derive(Default)]
+pub struct GenesisHeader {
+    pub coinbase: String,
+    pub base_fee_per_gas: String,
+    pub difficulty: String,
+    pub extra_data: String,
+    pub gas_limit: String,
+    pub nonce: String,
+    pub mixhash: String,
+    pub parent_hash: String,
+    pub timestamp: String,
+}
+
+/// Implements the conversions between `NetworkGenesis` and a genesis format struct with the
+/// `config`, `alloc`, header and `alloc_overrides` fields of `GethGenesisConfig`.
+macro_rules! network_genesis_conversions {
+    ($genesis:ident) => {
+        impl From<$crate::el::network_genesis::NetworkGenesis> for $genesis {
+            fn from(genesis: $crate::el::network_genesis::NetworkGenesis) -> Self {
+                let header = genesis.header;
+                $genesis {
+                    config: genesis.config,
+                    alloc: genesis.alloc,
+                    coinbase: header.coinbase,
+                    base_fee_per_gas: header.base_fee_per_gas,
+                    difficulty: header.difficulty,
+                    extra_data: header.extra_data,
+                    gas_limit: header.gas_limit,
+                    nonce: header.nonce,
+                    mixhash: header.mixhash,
+                    parent_hash: header.parent_hash,
+                    timestamp: header.timestamp,
+                    alloc_overrides: Vec::new(),
+                }
+            }
+        }
+
+        impl From<$genesis> for $crate::el::network_genesis::NetworkGenesis {
+            fn from(genesis: $genesis) -> Self {
+                $crate::el::network_genesis::NetworkGenesis {
+                    config: genesis.config,
+                    alloc: genesis.alloc,
+                    header: $crate::el::network_genesis::GenesisHeader {
+                        coinbase: genesis.coinbase,
+                        base_fee_per_gas: genesis.base_fee_per_gas,
+                        difficulty: genesis.difficulty,
+                        extra_data: genesis.extra_data,
+                        gas_limit: genesis.gas_limit,
+                        nonce: genesis.nonce,
+                        mixhash: genesis.mixhash,
+                        parent_hash: genesis.parent_hash,
+                        timestamp: genesis.timestamp,
+                    },
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use network_genesis_conversions;
@@ -4,17 +4,38 @@ use std::path::Path;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use super::accounts::PremineAccount;
+use super::allocation::AllocOverride;
+use super::format_registry::Fork;
 use super::genesis_config::GenesisConfig;
+use super::network_genesis::NetworkGenesis;
 
 
-pub trait Genesis: SerializableToFile {
-    fn create_genesis(genesis: &GenesisConfig) -> Self;
+/// An EL genesis format, registered by name in a `FormatRegistry`. Converting into the shared
+/// `NetworkGenesis` lets `verify` and `diff` compare the files of every format.
+pub trait Genesis: SerializableToFile + Into<NetworkGenesis> {
+    /// Name the format is selected by, e.g. `geth`.
+    const NAME: &'static str;
+    /// File the genesis is written to in the metadata folder.
+    const FILE_NAME: &'static str;
+    /// EL clients that read this format, selected with `EL_CLIENTS`.
+    const CLIENTS: &'static [&'static str];
+    /// Forks this format can schedule; generation fails if the config schedules any other.
+    const SUPPORTED_FORKS: &'static [Fork];
 
+    /// Creates the genesis of `genesis`, allocating the `premine` accounts derived from it.
+    fn create_genesis(
+        genesis: &GenesisConfig,
+        premine: &[PremineAccount],
+    ) -> anyhow::Result<Self>;
+
+    /// Alloc entries that replaced or were merged into an existing entry while building.
+    fn alloc_overrides(&self) -> &[AllocOverride];
 }
 
 pub trait SerializableToFile: Serialize + Sized + DeserializeOwned {
     fn read_from_file<P: AsRef<Path>>(path: P) -> serde_json::Result<Self> {
-        let file = File::open(path).unwrap();
+        let file = File::open(path).map_err(serde_json::Error::io)?;
         serde_json::from_reader(file)
     }
     fn save_to_file<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
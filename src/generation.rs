@@ -1,39 +1,79 @@
 use anyhow::{Context, Result};
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::el::{besu_generation::BesuGenesisConfig, chainspec_generation::ChainspecGenesisConfig, genesis_config::{GenesisConfig, GenesisConfigBuilder}, geth_generation::GethGenesisConfig};
-use crate::el::serializabe_to_file::{Genesis, SerializableToFile};
+use crate::el::format_registry::FormatRegistry;
+use crate::el::genesis_config::{GenesisConfig, GenesisConfigBuilder};
+use crate::el::genesis_hash::genesis_block_hash;
+use crate::generator::{ArtifactSink, DirectorySink, GenesisGenerator};
+use crate::manifest::Manifest;
 
 
 pub fn get_genesis_config(context: HashMap<String, String>) -> Result<GenesisConfig> {
     let genesis_config = GenesisConfigBuilder::new()
         .from_hashmap(context)?
         .with_default_premine()
-        .build();
-    return Ok(genesis_config);
+        .build()?;
+    Ok(genesis_config)
 }
 
 pub fn gen_el_config(context: HashMap<String, String>, metadata_folder: PathBuf) -> Result<()> {
     fs::create_dir_all(&metadata_folder)
         .context("Failed to create metadata folder")?;
 
-    let genesis_config = get_genesis_config(context)?;
-    let geth_genesis_path = metadata_folder.join("genesis.json");
-    GethGenesisConfig::create_genesis(&genesis_config).save_if_not_exists(geth_genesis_path);
+    let artifacts = GenesisGenerator::new(get_genesis_config(context)?).generate()?;
+    for el_genesis in &artifacts.el_genesis {
+        for alloc_override in el_genesis.genesis.alloc_overrides() {
+            println!("{}: {}", el_genesis.file_name, alloc_override);
+        }
+    }
+    artifacts.write_el_to(&mut DirectorySink::new(metadata_folder))
+}
 
-    let chainspec_genesis_path = metadata_folder.join("chainspec.json");
-    ChainspecGenesisConfig::create_genesis(&genesis_config).save_if_not_exists(chainspec_genesis_path);
+/// Writes the CL `config.yaml` and the files of the selected CL clients. The `genesis.ssz`
+/// state needs signed deposits of the validator keys, which are not generated yet.
+pub fn gen_cl_config(context: HashMap<String, String>, metadata_folder: PathBuf) -> Result<()> {
+    fs::create_dir_all(&metadata_folder)
+        .context("Failed to create metadata folder")?;
+    GenesisGenerator::new(get_genesis_config(context)?)
+        .generate_cl()
+        .write_to(&mut DirectorySink::new(metadata_folder))
+}
 
-    let besu_genesis_path = metadata_folder.join("besu.json");
-    BesuGenesisConfig::create_genesis(&genesis_config).save_if_not_exists(besu_genesis_path);
+/// Writes `deposit_contract.txt` and, if an EL genesis file was generated into the metadata
+/// folder before, `deposit_contract_block_hash.txt` with the hash of its genesis block, which
+/// deploys the deposit contract.
+pub fn gen_shared_files(context: HashMap<String, String>, metadata_folder: PathBuf) -> Result<()> {
+    fs::create_dir_all(&metadata_folder)
+        .context("Failed to create metadata folder")?;
+    let genesis_config = get_genesis_config(context)?;
+    let mut sink = DirectorySink::new(&metadata_folder);
+    sink.write_file(
+        Path::new("deposit_contract.txt"),
+        format!("{}\n", genesis_config.deposit_contract_address).as_bytes(),
+    )?;
 
+    let registry = FormatRegistry::default();
+    let el_genesis = registry
+        .formats()
+        .map(|format| (format, metadata_folder.join(format.file_name)))
+        .find(|(_, path)| path.exists());
+    if let Some((format, path)) = el_genesis {
+        let genesis_hash = genesis_block_hash(&format.read_network(&path)?)?;
+        sink.write_file(
+            Path::new("deposit_contract_block_hash.txt"),
+            format!("{:?}\n", genesis_hash).as_bytes(),
+        )?;
+    }
     Ok(())
 }
 
-pub fn gen_cl_config(context: HashMap<String, String>, metadata_folder: PathBuf) -> Result<()> {
-    todo!()
+/// Writes `manifest.json` describing the files already generated into the metadata folder.
+/// A staging folder starts as a copy of the metadata folder and replaces it on commit, so the
+/// manifest of the staging folder lists every committed file, also those of earlier runs.
+pub fn gen_manifest(context: HashMap<String, String>, metadata_folder: PathBuf) -> Result<()> {
+    Manifest::new(&get_genesis_config(context)?, &metadata_folder)?.write(&metadata_folder)
 }
-
-pub fn gen_shared_files(context: HashMap<String, String>, metadata_folder: PathBuf) -> Result<()> {
-    todo!()
-}
\ No newline at end of file
@@ -0,0 +1,408 @@
+use anyhow::{Context, Result};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ethers::core::rand::rngs::StdRng;
+
+use crate::cl::{
+    client_files::cl_client_files,
+    config::{cl_config, render_cl_config, CL_CONFIG_FILE},
+};
+use crate::el::{
+    accounts::{derive_premine, keystore_rng, Accounts},
+    format_registry::{FormatRegistry, GeneratedGenesis},
+    genesis_config::GenesisConfig,
+    serializabe_to_file::Genesis,
+};
+
+/// Generates every artifact of a network in memory, without touching the filesystem.
+pub struct GenesisGenerator {
+    genesis_config: GenesisConfig,
+    registry: FormatRegistry,
+}
+
+impl GenesisGenerator {
+    pub fn new(genesis_config: GenesisConfig) -> Self {
+        GenesisGenerator {
+            genesis_config,
+            registry: FormatRegistry::default(),
+        }
+    }
+
+    /// Generates the EL genesis formats of `registry` instead of the built-in ones,
+    /// narrowed down to the configured `el_clients`.
+    pub fn with_registry(mut self, registry: FormatRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    pub fn genesis_config(&self) -> &GenesisConfig {
+        &self.genesis_config
+    }
+
+    pub fn generate(&self) -> Result<GenesisArtifacts> {
+        let genesis_config = &self.genesis_config;
+        let mut el_genesis = Vec::new();
+        let registry = self.registry.select(&genesis_config.el_clients)?;
+        let premine =
+            derive_premine(genesis_config).context("Failed to derive premine accounts")?;
+        for format in registry.formats() {
+            el_genesis.push(ElGenesis {
+                name: format.name,
+                file_name: format.file_name,
+                genesis: format.create_genesis(genesis_config, &premine)?,
+            });
+        }
+        let accounts = Accounts::from_genesis_config(genesis_config, &premine)
+            .context("Failed to collect premine accounts")?;
+        let keystore_password = genesis_config
+            .el_premine_keystores
+            .then(|| genesis_config.el_premine_keystore_password.clone());
+
+        Ok(GenesisArtifacts {
+            el_genesis,
+            accounts,
+            keystore_password,
+            seed: genesis_config.seed,
+            cl: self.generate_cl(),
+            extra_files: BTreeMap::new(),
+        })
+    }
+
+    /// Generates only the CL files, without deriving any EL account.
+    pub fn generate_cl(&self) -> ClArtifacts {
+        ClArtifacts {
+            config: render_cl_config(&cl_config(&self.genesis_config)),
+            client_files: cl_client_files(&self.genesis_config),
+        }
+    }
+}
+
+/// The generated network, free to inspect and modify before it is written by an `ArtifactSink`.
+/// The CL `genesis.ssz` is not generated by this crate yet and can be added as an extra file.
+pub struct GenesisArtifacts {
+    /// One genesis per registered format, in registration order.
+    pub el_genesis: Vec<ElGenesis>,
+    pub accounts: Accounts,
+    /// Password of the account keystores, `None` when no keystores are written.
+    pub keystore_password: Option<String>,
+    /// Seed of the keystore randomness, random if `None`.
+    pub seed: Option<u64>,
+    pub cl: ClArtifacts,
+    /// Additional files by path relative to the output folder.
+    pub extra_files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+/// The CL files of the network.
+pub struct ClArtifacts {
+    /// The rendered `config.yaml`.
+    pub config: String,
+    /// Files of the selected CL clients by file name.
+    pub client_files: Vec<(&'static str, String)>,
+}
+
+impl ClArtifacts {
+    pub fn write_to<S: ArtifactSink>(&self, sink: &mut S) -> Result<()> {
+        sink.write_file(Path::new(CL_CONFIG_FILE), self.config.as_bytes())?;
+        for (file, contents) in &self.client_files {
+            sink.write_file(Path::new(file), contents.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+pub struct ElGenesis {
+    pub name: &'static str,
+    pub file_name: &'static str,
+    pub genesis: Box<dyn GeneratedGenesis>,
+}
+
+impl GenesisArtifacts {
+    /// The genesis generated by format `G`, if it was registered.
+    pub fn el_genesis<G: Genesis + 'static>(&self) -> Option<&G> {
+        self.el_genesis
+            .iter()
+            .find(|el_genesis| el_genesis.name == G::NAME)
+            .and_then(|el_genesis| el_genesis.genesis.as_any().downcast_ref())
+    }
+
+    pub fn el_genesis_mut<G: Genesis + 'static>(&mut self) -> Option<&mut G> {
+        self.el_genesis
+            .iter_mut()
+            .find(|el_genesis| el_genesis.name == G::NAME)
+            .and_then(|el_genesis| el_genesis.genesis.as_any_mut().downcast_mut())
+    }
+
+    /// Writes every artifact: the EL files, the CL files and the extra files.
+    pub fn write_to<S: ArtifactSink>(&self, sink: &mut S) -> Result<()> {
+        self.write_el_to(sink)?;
+        self.cl.write_to(sink)?;
+        for (path, contents) in &self.extra_files {
+            sink.write_file(path, contents)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the EL genesis files, `accounts.json` and the keystores.
+    pub fn write_el_to<S: ArtifactSink>(&self, sink: &mut S) -> Result<()> {
+        for el_genesis in &self.el_genesis {
+            sink.write_file(
+                Path::new(el_genesis.file_name),
+                &el_genesis.genesis.to_json()?,
+            )?;
+        }
+        sink.write_file(
+            Path::new("accounts.json"),
+            &serde_json::to_vec(&self.accounts)?,
+        )?;
+        if let Some(password) = &self.keystore_password {
+            let mut rng = keystore_rng(self.seed);
+            sink.write_keystores(Path::new("keystores"), &self.accounts, password, &mut rng)?;
+        }
+        Ok(())
+    }
+}
+
+/// Destination of `GenesisArtifacts::write_to`. Paths are relative to the sink's root.
+pub trait ArtifactSink {
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    /// Encrypts a keystore per account into the folder `dir`.
+    fn write_keystores(
+        &mut self,
+        dir: &Path,
+        accounts: &Accounts,
+        password: &str,
+        rng: &mut StdRng,
+    ) -> Result<()>;
+}
+
+/// Writes artifacts below a folder, keeping files that already exist.
+pub struct DirectorySink {
+    root: PathBuf,
+}
+
+impl DirectorySink {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        DirectorySink { root: root.into() }
+    }
+}
+
+impl ArtifactSink for DirectorySink {
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+        let path = self.root.join(path);
+        if path.exists() {
+            println!("{} already exists. Skipping generation...", path.display());
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create folder {}", parent.display()))?;
+        }
+        fs::write(&path, contents).with_context(|| format!("Cannot write {}", path.display()))
+    }
+
+    fn write_keystores(
+        &mut self,
+        dir: &Path,
+        accounts: &Accounts,
+        password: &str,
+        rng: &mut StdRng,
+    ) -> Result<()> {
+        accounts.write_keystores(self.root.join(dir), password, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::{
+        accounts::Account, besu_generation::BesuGenesisConfig,
+        chainspec_generation::ChainspecGenesisConfig, geth_generation::GethGenesisConfig,
+    };
+    use crate::el::{fixtures::geth_genesis, genesis_config::GenesisConfigBuilder};
+    use ethers::types::U256;
+    use serde_json::json;
+    use serial_test::serial;
+    use std::env;
+
+    fn artifacts() -> GenesisArtifacts {
+        let genesis = geth_genesis(
+            0,
+            json!({"ethash": {"b": "2", "a": "1", "c": "3"}}),
+            json!({
+                "0x8943545177806ed17b9f23f0a21ee5948ecaa776": {"balance": "1ETH"},
+                "0x4242424242424242424242424242424242424242": {
+                    "balance": "0",
+                    "code": "0x60",
+                    "storage": {"0x02": "0x01", "0x01": "0x02", "0x03": "0x03"}
+                },
+                "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266": {"balance": "0x10"},
+                "0x000000000000000000000000000000000000dead": {"balance": "1"}
+            }),
+        );
+        let geth: GethGenesisConfig = serde_json::from_value(genesis.clone()).unwrap();
+        let chainspec: ChainspecGenesisConfig = serde_json::from_value(genesis.clone()).unwrap();
+        let besu: BesuGenesisConfig = serde_json::from_value(genesis).unwrap();
+        GenesisArtifacts {
+            el_genesis: vec![
+                ElGenesis {
+                    name: "geth",
+                    file_name: "genesis.json",
+                    genesis: Box::new(geth),
+                },
+                ElGenesis {
+                    name: "chainspec",
+                    file_name: "chainspec.json",
+                    genesis: Box::new(chainspec),
+                },
+                ElGenesis {
+                    name: "besu",
+                    file_name: "besu.json",
+                    genesis: Box::new(besu),
+                },
+            ],
+            accounts: Accounts(vec![Account {
+                address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+                    .parse()
+                    .unwrap(),
+                private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                    .to_string(),
+                derivation_path: None,
+                balance: U256::one(),
+            }]),
+            keystore_password: Some("secret".to_string()),
+            seed: Some(1),
+            cl: ClArtifacts {
+                config: "PRESET_BASE: mainnet\n".to_string(),
+                client_files: vec![("deploy_block.txt", "0\n".to_string())],
+            },
+            extra_files: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_writes_modified_artifacts_to_directory() {
+        let mut artifacts = artifacts();
+        artifacts
+            .el_genesis_mut::<GethGenesisConfig>()
+            .unwrap()
+            .gas_limit = "0x1c9c380".to_string();
+        artifacts.cl.config = "PRESET_BASE: minimal\n".to_string();
+        artifacts
+            .extra_files
+            .insert(PathBuf::from("cl/boot_enr.yaml"), b"[]\n".to_vec());
+        let dir = tempfile::tempdir().unwrap();
+
+        artifacts
+            .write_to(&mut DirectorySink::new(dir.path()))
+            .unwrap();
+
+        let geth: serde_json::Value =
+            serde_json::from_slice(&fs::read(dir.path().join("genesis.json")).unwrap()).unwrap();
+        assert_eq!(geth["gasLimit"], "0x1c9c380");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("config.yaml")).unwrap(),
+            "PRESET_BASE: minimal\n"
+        );
+        for file in [
+            "chainspec.json",
+            "besu.json",
+            "accounts.json",
+            "deploy_block.txt",
+            "cl/boot_enr.yaml",
+        ] {
+            assert!(dir.path().join(file).exists(), "{} missing", file);
+        }
+        let keystores = fs::read_dir(dir.path().join("keystores")).unwrap();
+        assert_eq!(keystores.count(), 1);
+    }
+
+    fn read_files(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        let mut files = BTreeMap::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(read_files(&path));
+            } else {
+                files.insert(path.clone(), fs::read(&path).unwrap());
+            }
+        }
+        files
+    }
+
+    fn generate_into(dir: &Path) {
+        let genesis_config = GenesisConfigBuilder::new()
+            .preset_base("minimal".to_string())
+            .chain_id(1337)
+            .deposit_contract_address("0x4242424242424242424242424242424242424242".to_string())
+            .mnemonic("test test test test test test test test test test test junk".to_string())
+            .el_premine("m/44'/60'/0'/0/100".to_string(), "5ETH".to_string())
+            .el_premine_count(2)
+            .el_premine_keystores(true)
+            .el_premine_keystore_password("password".to_string())
+            .genesis_timestamp(1700000000)
+            .genesis_delay(60)
+            .slot_duration_in_seconds(6)
+            .capella_fork_epoch(Some(0))
+            .deneb_fork_epoch(Some(1))
+            .seed(Some(7))
+            .build()
+            .unwrap();
+        GenesisGenerator::new(genesis_config)
+            .generate()
+            .unwrap()
+            .write_to(&mut DirectorySink::new(dir))
+            .unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_two_generator_runs_write_identical_bytes() {
+        // The built-in formats read their templates from ../data relative to the working folder.
+        let dir = tempfile::tempdir().unwrap();
+        let templates = dir.path().join("data/devnet");
+        fs::create_dir_all(&templates).unwrap();
+        let template = geth_genesis(0, json!({}), json!({})).to_string();
+        for file in [
+            "geth_genesis.json",
+            "besu_genesis.json",
+            "chainspec_genesis.json",
+        ] {
+            fs::write(templates.join(file), &template).unwrap();
+        }
+        let work = dir.path().join("work");
+        let first = dir.path().join("first");
+        let second = dir.path().join("second");
+        fs::create_dir(&work).unwrap();
+
+        let cwd = env::current_dir().unwrap();
+        env::set_current_dir(&work).unwrap();
+        generate_into(&first);
+        generate_into(&second);
+        env::set_current_dir(cwd).unwrap();
+
+        let first_files = read_files(&first);
+        let second_files = read_files(&second);
+        // Three genesis files, accounts.json, a keystore per premine account, config.yaml and
+        // the files of the lighthouse and nimbus CL clients.
+        assert_eq!(first_files.len(), 10);
+        assert_eq!(first_files.len(), second_files.len());
+        for ((first_path, first_bytes), (second_path, second_bytes)) in
+            first_files.iter().zip(&second_files)
+        {
+            assert_eq!(
+                first_path.strip_prefix(&first).unwrap(),
+                second_path.strip_prefix(&second).unwrap()
+            );
+            assert!(
+                first_bytes == second_bytes,
+                "{} differs",
+                first_path.display()
+            );
+        }
+    }
+}
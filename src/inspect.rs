@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use ethers::{
+    types::{Address, U256},
+    utils::{format_ether, to_checksum},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{fmt::Write, path::Path};
+
+use crate::{
+    cl::genesis_state::GenesisState,
+    el::{
+        allocation::{deserialize_alloc, Alloc},
+        format_registry::FormatRegistry,
+        genesis_config::GenesisConfig,
+        genesis_hash::parse_u64,
+    },
+    timeline::format_utc,
+};
+
+/// The fields of any EL genesis format that `inspect` reports on.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenesisOverview {
+    config: Map<String, Value>,
+    #[serde(deserialize_with = "deserialize_alloc")]
+    alloc: Alloc,
+    timestamp: String,
+}
+
+/// Describes an EL genesis file or an SSZ genesis state. Epochs and the preset of SSZ states
+/// are taken from `genesis_config`.
+pub fn inspect(path: &Path, genesis_config: &GenesisConfig) -> Result<String> {
+    if path.extension().is_some_and(|extension| extension == "ssz") {
+        inspect_state(path, genesis_config)
+    } else {
+        inspect_el_genesis(path, genesis_config)
+    }
+}
+
+fn inspect_el_genesis(path: &Path, genesis_config: &GenesisConfig) -> Result<String> {
+    let registry = FormatRegistry::default();
+    let (format, genesis) = registry.detect(path)?;
+    let overview: GenesisOverview = serde_json::from_slice(&genesis.to_json()?)
+        .with_context(|| format!("Cannot read the genesis fields of {}", path.display()))?;
+    let genesis_timestamp = parse_u64(&overview.timestamp).context("Invalid genesis timestamp")?;
+
+    let mut report = String::new();
+    writeln!(report, "file: {} ({} genesis)", path.display(), format.name)?;
+    if let Some(chain_id) = overview.config.get("chainId") {
+        writeln!(report, "chain id: {}", chain_id)?;
+    }
+    writeln!(
+        report,
+        "genesis timestamp: {} ({})",
+        genesis_timestamp,
+        format_utc(genesis_timestamp)
+    )?;
+
+    let mut forks: Vec<(&String, u64)> = overview
+        .config
+        .iter()
+        .filter(|(key, _)| key.ends_with("Time"))
+        .filter_map(|(key, value)| Some((key, value.as_u64()?)))
+        .collect();
+    forks.sort_by_key(|(key, time)| (*time, key.to_string()));
+    writeln!(report, "forks:")?;
+    for (key, time) in forks {
+        writeln!(
+            report,
+            "  {}: {}",
+            key,
+            describe_fork_time(time, genesis_timestamp, genesis_config)
+        )?;
+    }
+
+    let total_supply = overview
+        .alloc
+        .values()
+        .fold(U256::zero(), |total, allocation| total + allocation.balance);
+    writeln!(report, "alloc accounts: {}", overview.alloc.len())?;
+    writeln!(
+        report,
+        "total supply: {} wei ({} ETH)",
+        total_supply,
+        format_ether(total_supply)
+    )?;
+
+    let deposit_contract = overview
+        .config
+        .get("depositContractAddress")
+        .and_then(Value::as_str)
+        .and_then(|address| address.parse::<Address>().ok());
+    writeln!(report, "contracts:")?;
+    for (address, allocation) in &overview.alloc {
+        let Some(code) = allocation.code.as_ref().filter(|code| !code.is_empty()) else {
+            continue;
+        };
+        let label = if Some(*address) == deposit_contract {
+            " (deposit contract)"
+        } else {
+            ""
+        };
+        writeln!(
+            report,
+            "  {}{}: {} bytes of code, {} storage slots",
+            to_checksum(address, None),
+            label,
+            code.len(),
+            allocation
+                .storage
+                .as_ref()
+                .map_or(0, |storage| storage.len())
+        )?;
+    }
+    Ok(report)
+}
+
+fn inspect_state(path: &Path, genesis_config: &GenesisConfig) -> Result<String> {
+    let state = GenesisState::read(path, &genesis_config.preset_base)?;
+    let effective_balance: u64 = state
+        .validators
+        .iter()
+        .map(|validator| validator.effective_balance)
+        .sum();
+
+    let mut report = String::new();
+    writeln!(
+        report,
+        "file: {} (beacon state, {} preset)",
+        path.display(),
+        genesis_config.preset_base
+    )?;
+    writeln!(
+        report,
+        "genesis time: {} ({})",
+        state.genesis_time,
+        format_utc(state.genesis_time)
+    )?;
+    writeln!(report, "fork version: {}", state.fork_version)?;
+    writeln!(
+        report,
+        "genesis validators root: {:?}",
+        state.genesis_validators_root
+    )?;
+    writeln!(
+        report,
+        "latest block body root: {:?}",
+        state.latest_block_body_root
+    )?;
+    writeln!(
+        report,
+        "validators: {} ({} gwei effective balance)",
+        state.validators.len(),
+        effective_balance
+    )?;
+    if let Some(header) = &state.execution_payload_header {
+        writeln!(
+            report,
+            "execution block: {:?} (number {}, timestamp {})",
+            header.block_hash, header.block_number, header.timestamp
+        )?;
+    }
+    Ok(report)
+}
+
+/// The fork time as a date and the CL epoch it falls in.
+fn describe_fork_time(time: u64, genesis_timestamp: u64, genesis_config: &GenesisConfig) -> String {
+    let cl_genesis_time = genesis_timestamp + genesis_config.genesis_delay;
+    if time <= genesis_timestamp {
+        return format!("{} (at genesis)", time);
+    }
+    if time < cl_genesis_time {
+        return format!("{} ({}, before CL genesis)", time, format_utc(time));
+    }
+    let seconds_per_epoch = genesis_config.seconds_per_epoch();
+    let since_genesis = time - cl_genesis_time;
+    let mut epoch = format!("epoch {}", since_genesis / seconds_per_epoch);
+    if !since_genesis.is_multiple_of(seconds_per_epoch) {
+        write!(epoch, " + {}s", since_genesis % seconds_per_epoch).unwrap();
+    }
+    format!("{} ({}, {})", time, format_utc(time), epoch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::fixtures::geth_genesis;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn test_inspects_el_genesis() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("besu.json");
+        let genesis = geth_genesis(
+            1700000000,
+            json!({"shanghaiTime": 1700000000, "cancunTime": 1700000500}),
+            json!({
+                "0x4242424242424242424242424242424242424242": {
+                    "balance": "0", "code": "0x6000", "storage": {"0x01": "0x02"}
+                },
+                "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266": {"balance": "1ETH"}
+            }),
+        );
+        fs::write(&path, genesis.to_string()).unwrap();
+        let genesis_config = GenesisConfig {
+            genesis_delay: 96,
+            preset_base: "minimal".to_string(),
+            slot_duration_in_seconds: 6,
+            ..Default::default()
+        };
+
+        let report = inspect(&path, &genesis_config).unwrap();
+
+        assert!(report.contains("(besu genesis)"), "{}", report);
+        assert!(report.contains("chain id: 1337"), "{}", report);
+        assert!(report.contains("  shanghaiTime: 1700000000 (at genesis)\n"));
+        assert!(
+            report.contains("  cancunTime: 1700000500 (2023-11-14 22:21:40 UTC, epoch 8 + 20s)\n")
+        );
+        assert!(report.contains("alloc accounts: 2"));
+        assert!(report.contains("total supply: 1000000000000000000 wei (1.000000000000000000 ETH)"));
+        assert!(report.contains(
+            "  0x4242424242424242424242424242424242424242 (deposit contract): 2 bytes of code, 1 storage slots"
+        ));
+    }
+}
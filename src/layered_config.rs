@@ -0,0 +1,207 @@
+use anyhow::{bail, Result};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config_file::read_config_with_lines,
+    el::{config_keys::KNOWN_KEYS, genesis_config::builtin_defaults},
+};
+
+/// Where a resolved config value came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File { path: PathBuf, line: Option<usize> },
+    Env(String),
+    Flag,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "built-in default"),
+            Source::File {
+                path,
+                line: Some(line),
+            } => write!(f, "{}:{}", path.display(), line),
+            Source::File { path, line: None } => write!(f, "{}", path.display()),
+            Source::Env(name) => write!(f, "environment variable {}", name),
+            Source::Flag => write!(f, "--set flag"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedValue {
+    pub value: String,
+    pub source: Source,
+}
+
+/// Resolves config keys from layers applied in increasing precedence:
+/// built-in defaults, the config file, environment variables, then `--set KEY=VALUE` flags.
+#[derive(Debug, Default)]
+pub struct LayeredConfig {
+    values: BTreeMap<String, ResolvedValue>,
+}
+
+impl LayeredConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_defaults(mut self) -> Self {
+        for (key, value) in builtin_defaults() {
+            self.set(key, value, Source::Default);
+        }
+        self
+    }
+
+    pub fn with_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        for (key, (value, line)) in read_config_with_lines(path)? {
+            let source = Source::File {
+                path: path.to_path_buf(),
+                line,
+            };
+            self.set(key, value, source);
+        }
+        Ok(self)
+    }
+
+    /// Overrides every config key, and every other key already set by the defaults or the file,
+    /// with the environment variable of the same name, if it is set.
+    pub fn with_env(mut self) -> Self {
+        let mut keys: Vec<String> = self.values.keys().cloned().collect();
+        keys.extend(KNOWN_KEYS.iter().map(|key| key.to_string()));
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            if let Ok(value) = env::var(&key) {
+                self.set(key.clone(), value, Source::Env(key));
+            }
+        }
+        self
+    }
+
+    pub fn with_overrides(mut self, overrides: &[String]) -> Result<Self> {
+        for assignment in overrides {
+            let Some((key, value)) = assignment.split_once('=') else {
+                bail!("Invalid --set {}, expected KEY=VALUE", assignment);
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                bail!("Invalid --set {}, key is empty", assignment);
+            }
+            self.set(key.to_string(), value.to_string(), Source::Flag);
+        }
+        Ok(self)
+    }
+
+    fn set(&mut self, key: String, value: String, source: Source) {
+        self.values.insert(key, ResolvedValue { value, source });
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ResolvedValue> {
+        self.values.get(key)
+    }
+
+    /// Resolved values in key order.
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &ResolvedValue)> {
+        self.values.iter()
+    }
+
+    pub fn context(&self) -> HashMap<String, String> {
+        self.values
+            .iter()
+            .map(|(key, resolved)| (key.clone(), resolved.value.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::fs;
+
+    #[test]
+    #[serial]
+    fn test_layers_apply_in_precedence_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("values.env");
+        fs::write(
+            &path,
+            "export CHAIN_ID=\"1337\"\nexport LAYERED_GENESIS_DELAY=\"60\"\nexport PRESET_BASE=\"mainnet\"\n",
+        )
+        .unwrap();
+        env::set_var("LAYERED_GENESIS_DELAY", "120");
+
+        let config = LayeredConfig::new()
+            .with_defaults()
+            .with_file(&path)
+            .unwrap()
+            .with_env()
+            .with_overrides(&["PRESET_BASE=minimal".to_string()])
+            .unwrap();
+        env::remove_var("LAYERED_GENESIS_DELAY");
+
+        let chain_id = config.get("CHAIN_ID").unwrap();
+        assert_eq!(chain_id.value, "1337");
+        assert_eq!(
+            chain_id.source,
+            Source::File {
+                path: path.clone(),
+                line: Some(1)
+            }
+        );
+        let genesis_delay = config.get("LAYERED_GENESIS_DELAY").unwrap();
+        assert_eq!(genesis_delay.value, "120");
+        assert_eq!(
+            genesis_delay.source,
+            Source::Env("LAYERED_GENESIS_DELAY".to_string())
+        );
+        let preset_base = config.get("PRESET_BASE").unwrap();
+        assert_eq!(preset_base.value, "minimal");
+        assert_eq!(preset_base.source, Source::Flag);
+        assert_eq!(
+            config.get("EL_PREMINE_COUNT").unwrap().source,
+            Source::Default
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_env_sets_known_keys_missing_from_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("values.yaml");
+        fs::write(&path, "chain_id: 1337\n").unwrap();
+        env::set_var("SHADOW_FORK_RPC", "http://localhost:8545");
+
+        let config = LayeredConfig::new()
+            .with_defaults()
+            .with_file(&path)
+            .unwrap()
+            .with_env();
+        env::remove_var("SHADOW_FORK_RPC");
+
+        let shadow_fork_rpc = config.get("SHADOW_FORK_RPC").unwrap();
+        assert_eq!(shadow_fork_rpc.value, "http://localhost:8545");
+        assert_eq!(
+            shadow_fork_rpc.source,
+            Source::Env("SHADOW_FORK_RPC".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_overrides() {
+        assert!(LayeredConfig::new()
+            .with_overrides(&["CHAIN_ID".to_string()])
+            .is_err());
+        assert!(LayeredConfig::new()
+            .with_overrides(&["=1".to_string()])
+            .is_err());
+    }
+}
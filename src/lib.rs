@@ -1,5 +1,18 @@
+pub mod archive;
 pub mod args;
+pub mod cl;
 pub mod cli;
+pub mod config_file;
+pub mod convert;
 pub mod defaults_env;
+pub mod diff;
 pub mod generation;
+pub mod generator;
+pub mod inspect;
+pub mod layered_config;
+pub mod manifest;
+pub mod resolve;
+pub mod staging;
+pub mod timeline;
+pub mod verify;
 pub mod el;
\ No newline at end of file
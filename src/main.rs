@@ -1,5 +1,5 @@
 use ethereum_genesis_generator_rs::cli::run_cli;
 
-fn main() {
-    run_cli();
+fn main() -> anyhow::Result<()> {
+    run_cli()
 }
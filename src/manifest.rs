@@ -0,0 +1,357 @@
+use anyhow::{bail, Context, Result};
+use ethers::{types::H256, utils::hex};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cl::genesis_state::{fork_digest, GenesisState},
+    el::{
+        consensus_params::{ForkVersion, FAR_FUTURE_EPOCH},
+        format_registry::Fork,
+        genesis_config::GenesisConfig,
+        genesis_hash::genesis_block_hash,
+        geth_generation::GethGenesisConfig,
+        serializabe_to_file::Genesis,
+    },
+};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Every file of a metadata folder with its hash, and the values computed while generating it,
+/// so deployments can verify the folder without re-deriving anything.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub generator: GeneratorInfo,
+    pub files: Vec<ManifestFile>,
+    pub computed: ComputedValues,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeneratorInfo {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Relative to the metadata folder, with `/` separators.
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputedValues {
+    pub genesis_time: u64,
+    /// Hash of the block built from `genesis.json`, absent if it was not generated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub el_genesis_hash: Option<H256>,
+    /// Read from `genesis.ssz`, absent if it was not generated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genesis_validators_root: Option<H256>,
+    /// Activation timestamp per scheduled EL fork.
+    pub fork_timestamps: BTreeMap<String, u64>,
+    /// Fork digest per scheduled CL fork, known once the genesis validators root is.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fork_digests: BTreeMap<String, String>,
+    pub validator_count: u64,
+}
+
+impl Manifest {
+    /// Hashes every file below `folder` and computes the values of `genesis_config`.
+    pub fn new(genesis_config: &GenesisConfig, folder: &Path) -> Result<Self> {
+        let files = hash_files(folder)?;
+        let geth_genesis = folder.join(GethGenesisConfig::FILE_NAME);
+        let el_genesis_hash = if geth_genesis.exists() {
+            let genesis: GethGenesisConfig = serde_json::from_slice(&fs::read(&geth_genesis)?)
+                .with_context(|| format!("Cannot parse {}", geth_genesis.display()))?;
+            Some(genesis_block_hash(&genesis.into())?)
+        } else {
+            None
+        };
+        let genesis_ssz = folder.join("genesis.ssz");
+        let genesis_validators_root = if genesis_ssz.exists() {
+            Some(
+                GenesisState::read(&genesis_ssz, &genesis_config.preset_base)?
+                    .genesis_validators_root,
+            )
+        } else {
+            None
+        };
+        let fork_digests = match genesis_validators_root {
+            Some(root) => scheduled_fork_versions(genesis_config)
+                .into_iter()
+                .map(|(fork, version)| {
+                    let digest = fork_digest(version, root);
+                    (fork.to_string(), format!("0x{}", hex::encode(digest)))
+                })
+                .collect(),
+            None => BTreeMap::new(),
+        };
+        let mut fork_timestamps = BTreeMap::new();
+        for fork in Fork::scheduled(genesis_config) {
+            if let Some(epoch) = fork.activation_epoch(genesis_config) {
+                fork_timestamps.insert(fork.to_string(), genesis_config.activation_time(epoch));
+            }
+        }
+
+        Ok(Manifest {
+            generator: GeneratorInfo {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            files,
+            computed: ComputedValues {
+                genesis_time: genesis_config.genesis_timestamp + genesis_config.genesis_delay,
+                el_genesis_hash,
+                genesis_validators_root,
+                fork_timestamps,
+                fork_digests,
+                validator_count: genesis_config.consensus.number_of_validators,
+            },
+        })
+    }
+
+    /// Writes the manifest as `manifest.json` into `folder`.
+    pub fn write(&self, folder: &Path) -> Result<()> {
+        let path = folder.join(MANIFEST_FILE);
+        let contents = serde_json::to_string_pretty(self)? + "\n";
+        fs::write(&path, contents).with_context(|| format!("Cannot write {}", path.display()))
+    }
+
+    pub fn read(folder: &Path) -> Result<Self> {
+        let path = folder.join(MANIFEST_FILE);
+        let contents =
+            fs::read(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+        serde_json::from_slice(&contents)
+            .with_context(|| format!("Cannot parse {}", path.display()))
+    }
+
+    /// Checks that `folder` holds exactly the listed files with their hashes and sizes.
+    pub fn verify_files(&self, folder: &Path) -> Result<()> {
+        let actual = hash_files(folder)?;
+        let mut problems = Vec::new();
+        for expected in &self.files {
+            match actual.iter().find(|file| file.path == expected.path) {
+                None => problems.push(format!("{} is missing", expected.path)),
+                Some(file) if file.size != expected.size => problems.push(format!(
+                    "{} is {} bytes, expected {}",
+                    file.path, file.size, expected.size
+                )),
+                Some(file) if file.sha256 != expected.sha256 => problems.push(format!(
+                    "{} has sha256 {}, expected {}",
+                    file.path, file.sha256, expected.sha256
+                )),
+                Some(_) => {}
+            }
+        }
+        for file in &actual {
+            if !self.files.iter().any(|expected| expected.path == file.path) {
+                problems.push(format!("{} is not listed in {}", file.path, MANIFEST_FILE));
+            }
+        }
+        if !problems.is_empty() {
+            bail!(
+                "{} does not match {}:\n  {}",
+                folder.display(),
+                MANIFEST_FILE,
+                problems.join("\n  ")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Fork versions of the CL forks `genesis_config` schedules, by the name of the fork.
+fn scheduled_fork_versions(genesis_config: &GenesisConfig) -> Vec<(&'static str, ForkVersion)> {
+    let consensus = &genesis_config.consensus;
+    let mut versions = vec![
+        ("phase0", consensus.genesis_fork_version),
+        ("altair", consensus.altair_fork_version),
+        ("bellatrix", consensus.bellatrix_fork_version),
+    ];
+    let forks = [
+        (
+            "capella",
+            genesis_config.capella_fork_epoch,
+            consensus.capella_fork_version,
+        ),
+        (
+            "deneb",
+            genesis_config.deneb_fork_epoch,
+            consensus.deneb_fork_version,
+        ),
+        (
+            "electra",
+            genesis_config.electra_fork_epoch,
+            consensus.electra_fork_version,
+        ),
+    ];
+    for (fork, epoch, version) in forks {
+        if epoch.is_some() {
+            versions.push((fork, version));
+        }
+    }
+    if consensus.eip7594_fork_epoch != FAR_FUTURE_EPOCH {
+        versions.push(("eip7594", consensus.eip7594_fork_version));
+    }
+    versions
+}
+
+fn hash_files(folder: &Path) -> Result<Vec<ManifestFile>> {
+    let mut files = Vec::new();
+    for path in list_files(folder)? {
+        let relative = path.strip_prefix(folder)?;
+        if relative == Path::new(MANIFEST_FILE) {
+            continue;
+        }
+        let contents =
+            fs::read(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+        files.push(ManifestFile {
+            path: relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            sha256: format!("0x{:x}", Sha256::digest(&contents)),
+            size: contents.len() as u64,
+        });
+    }
+    Ok(files)
+}
+
+/// Files below `folder`, sorted by path.
+fn list_files(folder: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(list_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::staging::StagingDir;
+
+    #[test]
+    fn test_lists_files_with_hashes_and_fork_timestamps() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("keystores")).unwrap();
+        fs::write(dir.path().join("keystores/b.json"), "").unwrap();
+        fs::write(dir.path().join("accounts.json"), "abc").unwrap();
+        fs::write(dir.path().join(MANIFEST_FILE), "stale").unwrap();
+        let genesis_config = GenesisConfig {
+            genesis_timestamp: 100,
+            genesis_delay: 20,
+            slot_duration_in_seconds: 12,
+            preset_base: "mainnet".to_string(),
+            capella_fork_epoch: Some(0),
+            deneb_fork_epoch: Some(1),
+            ..Default::default()
+        };
+
+        let manifest = Manifest::new(&genesis_config, dir.path()).unwrap();
+
+        let paths: Vec<_> = manifest
+            .files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(paths, ["accounts.json", "keystores/b.json"]);
+        assert_eq!(
+            manifest.files[0].sha256,
+            "0xba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(manifest.files[0].size, 3);
+        assert_eq!(manifest.computed.genesis_time, 120);
+        assert_eq!(manifest.computed.el_genesis_hash, None);
+        assert_eq!(manifest.computed.genesis_validators_root, None);
+        assert!(manifest.computed.fork_digests.is_empty());
+        assert_eq!(
+            manifest.computed.fork_timestamps,
+            BTreeMap::from([
+                ("shanghai".to_string(), 120),
+                ("cancun".to_string(), 120 + 32 * 12)
+            ])
+        );
+
+        manifest.write(dir.path()).unwrap();
+        let written: Manifest =
+            serde_json::from_slice(&fs::read(dir.path().join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(written, manifest);
+    }
+
+    #[test]
+    fn test_lists_genesis_validators_root_and_fork_digests() {
+        let dir = tempfile::tempdir().unwrap();
+        // A minimal-preset phase0 state without validators; only the offsets of its
+        // variable-size fields and the genesis validators root are set.
+        let mut ssz = vec![0u8; 4400];
+        let historical_roots = 8 + 32 + 8 + 16 + 112 + 2 * 64 * 32;
+        for offset in [
+            historical_roots,
+            historical_roots + 88,
+            historical_roots + 92,
+        ] {
+            ssz[offset..offset + 4].copy_from_slice(&100u32.to_le_bytes());
+        }
+        let root: H256 = "0x4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95"
+            .parse()
+            .unwrap();
+        ssz[8..40].copy_from_slice(root.as_bytes());
+        fs::write(dir.path().join("genesis.ssz"), ssz).unwrap();
+        let mut genesis_config = GenesisConfig {
+            preset_base: "minimal".to_string(),
+            capella_fork_epoch: Some(0),
+            ..Default::default()
+        };
+        genesis_config.consensus.genesis_fork_version = ForkVersion([0, 0, 0, 0]);
+        genesis_config.consensus.capella_fork_version = ForkVersion([3, 0, 0, 0]);
+
+        let manifest = Manifest::new(&genesis_config, dir.path()).unwrap();
+
+        assert_eq!(manifest.computed.genesis_validators_root, Some(root));
+        let digests = &manifest.computed.fork_digests;
+        assert_eq!(
+            digests.keys().collect::<Vec<_>>(),
+            ["altair", "bellatrix", "capella", "phase0"]
+        );
+        assert_eq!(digests["phase0"], "0xb5303f2a");
+        assert_eq!(digests["capella"], "0xbba4da96");
+    }
+
+    #[test]
+    fn test_manifest_of_staging_folder_verifies_after_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("metadata");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("stale.json"), "old").unwrap();
+        let staging = StagingDir::begin(&target).unwrap();
+        fs::write(staging.path().join("accounts.json"), "[]").unwrap();
+
+        Manifest::new(&GenesisConfig::default(), staging.path())
+            .unwrap()
+            .write(staging.path())
+            .unwrap();
+        staging.commit().unwrap();
+
+        Manifest::read(&target)
+            .unwrap()
+            .verify_files(&target)
+            .unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use ethers::{signers::Signer, utils::to_checksum};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::el::{accounts::premine_accounts, genesis_config::GenesisConfig};
+
+/// Output format of `resolve`. Every format can be read back with `--config`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ResolvedFormat {
+    Json,
+    Yaml,
+    Env,
+}
+
+impl ResolvedFormat {
+    /// Extension `ConfigFormat::from_path` detects this format by.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ResolvedFormat::Json => "json",
+            ResolvedFormat::Yaml => "yaml",
+            ResolvedFormat::Env => "env",
+        }
+    }
+}
+
+/// Every config key of `genesis_config` together with the values computed from it:
+/// the genesis time, EL fork timestamps and the addresses of the premine accounts.
+pub fn resolve(genesis_config: &GenesisConfig) -> Result<BTreeMap<String, String>> {
+    let mut resolved = genesis_config.to_config_map()?;
+
+    let genesis_time = genesis_config.genesis_timestamp + genesis_config.genesis_delay;
+    resolved.insert("GENESIS_TIME".to_string(), genesis_time.to_string());
+    let fork_times = [
+        ("SHANGHAI_TIME", genesis_config.capella_fork_epoch),
+        ("CANCUN_TIME", genesis_config.deneb_fork_epoch),
+        ("PRAGUE_TIME", genesis_config.electra_fork_epoch),
+        ("PRAGUE_EOF_TIME", genesis_config.eof_activation_epoch),
+    ];
+    for (key, epoch) in fork_times {
+        if let Some(epoch) = epoch {
+            let time = genesis_config.activation_time(epoch);
+            resolved.insert(key.to_string(), time.to_string());
+        }
+    }
+
+    let mut derived_addrs = Vec::new();
+    for premine in premine_accounts(genesis_config)? {
+        let premine = premine?;
+        derived_addrs.push(json!({
+            "derivationPath": premine.derivation_path,
+            "address": to_checksum(&premine.wallet.address(), None),
+        }));
+    }
+    resolved.insert(
+        "EL_PREMINE_DERIVED_ADDRS".to_string(),
+        serde_json::to_string(&derived_addrs)?,
+    );
+    Ok(resolved)
+}
+
+pub fn render(resolved: &BTreeMap<String, String>, format: ResolvedFormat) -> Result<String> {
+    Ok(match format {
+        ResolvedFormat::Json => serde_json::to_string_pretty(resolved)? + "\n",
+        ResolvedFormat::Yaml => serde_yaml::to_string(resolved)?,
+        ResolvedFormat::Env => resolved
+            .iter()
+            .map(|(key, value)| format!("export {}=\"{}\"\n", key, escape_env_value(value)))
+            .collect(),
+    })
+}
+
+/// Escapes the characters that stay special inside double quotes of an env file.
+fn escape_env_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config_file::read_config,
+        el::{config_keys::ConfigMode, genesis_config::GenesisConfigBuilder},
+    };
+    use std::fs;
+
+    const MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    fn genesis_config() -> GenesisConfig {
+        let premine_addrs = "{\"0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed\": {\"balance\": \"1ETH\", \"secretKey\": \"0x01\"}}";
+        let context = [
+            ("PRESET_BASE", "minimal"),
+            ("CHAIN_ID", "1337"),
+            (
+                "DEPOSIT_CONTRACT_ADDRESS",
+                "0x4242424242424242424242424242424242424242",
+            ),
+            ("EL_AND_CL_MNEMONIC", MNEMONIC),
+            ("EL_PREMINE_COUNT", "2"),
+            ("EL_PREMINE_ADDRS", premine_addrs),
+            ("GENESIS_TIMESTAMP", "1700000000"),
+            ("GENESIS_DELAY", "60"),
+            ("SLOT_DURATION_IN_SECONDS", "6"),
+            ("ELECTRA_FORK_EPOCH", "2"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        GenesisConfigBuilder::new()
+            .config_mode(ConfigMode::Strict)
+            .from_hashmap(context)
+            .unwrap()
+            .with_default_premine()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_resolves_computed_values() {
+        let resolved = resolve(&genesis_config()).unwrap();
+
+        assert_eq!(resolved["GENESIS_TIME"], "1700000060");
+        assert_eq!(
+            resolved["PRAGUE_TIME"],
+            (1700000060 + 2 * 8 * 6).to_string()
+        );
+        assert!(!resolved.contains_key("CANCUN_TIME"));
+        assert!(resolved["EL_PREMINE_DERIVED_ADDRS"]
+            .contains("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"));
+        assert_eq!(resolved["NUMBER_OF_VALIDATORS"], "64");
+    }
+
+    #[test]
+    fn test_resolved_files_replay_to_the_same_config() {
+        let resolved = resolve(&genesis_config()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        for format in [
+            ResolvedFormat::Json,
+            ResolvedFormat::Yaml,
+            ResolvedFormat::Env,
+        ] {
+            let path = dir.path().join(format!("resolved.{}", format.extension()));
+            fs::write(&path, render(&resolved, format).unwrap()).unwrap();
+
+            let replayed = GenesisConfigBuilder::new()
+                .config_mode(ConfigMode::Strict)
+                .from_hashmap(read_config(&path).unwrap())
+                .unwrap()
+                .build()
+                .unwrap();
+            assert_eq!(resolve(&replayed).unwrap(), resolved, "{:?}", format);
+        }
+    }
+}
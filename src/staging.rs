@@ -0,0 +1,197 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// A sibling of the metadata folder that every artifact is written into first. It starts as a
+/// copy of the metadata folder, so files a run does not regenerate, such as the EL genesis files
+/// when only the CL config is generated, are kept. Nothing reaches the metadata folder until
+/// `commit`; dropping the staging directory without committing, also when generation fails or
+/// panics, removes it. A lock file keeps concurrent generators off the same metadata folder.
+pub struct StagingDir {
+    target: PathBuf,
+    staging: PathBuf,
+    lock: PathBuf,
+}
+
+impl StagingDir {
+    pub fn begin<P: AsRef<Path>>(target: P) -> Result<Self> {
+        let target = target.as_ref().to_path_buf();
+        let name = target
+            .file_name()
+            .with_context(|| format!("Invalid metadata folder {}", target.display()))?
+            .to_string_lossy()
+            .into_owned();
+        let parent = target.parent().unwrap_or_else(|| Path::new("."));
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create folder {}", parent.display()))?;
+
+        let lock = parent.join(format!(".{}.lock", name));
+        match OpenOptions::new().write(true).create_new(true).open(&lock) {
+            Ok(mut file) => writeln!(file, "{}", process::id())?,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => bail!(
+                "Another generator is writing {} (lock file {}). Remove the lock file if no generator is running",
+                target.display(),
+                lock.display()
+            ),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Cannot create lock file {}", lock.display()))
+            }
+        }
+
+        let staging = parent.join(format!(".{}.staging-{}", name, process::id()));
+        let staging_dir = StagingDir {
+            target,
+            staging,
+            lock,
+        };
+        if staging_dir.staging.exists() {
+            fs::remove_dir_all(&staging_dir.staging)?;
+        }
+        fs::create_dir_all(&staging_dir.staging).with_context(|| {
+            format!(
+                "Failed to create staging folder {}",
+                staging_dir.staging.display()
+            )
+        })?;
+        if staging_dir.target.exists() {
+            copy_dir(&staging_dir.target, &staging_dir.staging)?;
+        }
+        Ok(staging_dir)
+    }
+
+    /// Folder to write artifacts into.
+    pub fn path(&self) -> &Path {
+        &self.staging
+    }
+
+    /// Replaces the metadata folder with the staged one, which holds its files as of `begin` plus
+    /// those written since. An existing folder is renamed aside first and deleted once the staged
+    /// folder is in place, or restored if that rename fails.
+    pub fn commit(self) -> Result<()> {
+        let previous = self.staging.with_file_name(format!(
+            "{}.previous",
+            self.staging.file_name().unwrap().to_string_lossy()
+        ));
+        let replaced = self.target.exists();
+        if replaced {
+            fs::rename(&self.target, &previous).with_context(|| {
+                format!(
+                    "Cannot move {} aside to {}",
+                    self.target.display(),
+                    previous.display()
+                )
+            })?;
+        }
+        if let Err(e) = fs::rename(&self.staging, &self.target) {
+            if replaced {
+                fs::rename(&previous, &self.target).with_context(|| {
+                    format!(
+                        "Cannot restore {} from {}",
+                        self.target.display(),
+                        previous.display()
+                    )
+                })?;
+            }
+            return Err(e).with_context(|| {
+                format!(
+                    "Cannot move {} to {}",
+                    self.staging.display(),
+                    self.target.display()
+                )
+            });
+        }
+        if replaced {
+            fs::remove_dir_all(&previous)
+                .with_context(|| format!("Cannot remove {}", previous.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Copies the files and folders below `from` into the existing folder `to`.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in fs::read_dir(from).with_context(|| format!("Cannot read {}", from.display()))? {
+        let path = entry?.path();
+        let copy = to.join(path.file_name().unwrap());
+        if path.is_dir() {
+            fs::create_dir(&copy)
+                .with_context(|| format!("Failed to create folder {}", copy.display()))?;
+            copy_dir(&path, &copy)?;
+        } else {
+            fs::copy(&path, &copy)
+                .with_context(|| format!("Cannot copy {} to {}", path.display(), copy.display()))?;
+        }
+    }
+    Ok(())
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        if self.staging.exists() {
+            if let Err(e) = fs::remove_dir_all(&self.staging) {
+                eprintln!("warning: cannot remove {}: {}", self.staging.display(), e);
+            }
+        }
+        if let Err(e) = fs::remove_file(&self.lock) {
+            eprintln!(
+                "warning: cannot remove lock file {}: {}",
+                self.lock.display(),
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_keeps_files_not_written_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("metadata");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("genesis.json"), "old").unwrap();
+        fs::write(target.join("config.yaml"), "PRESET_BASE: minimal").unwrap();
+
+        let staging = StagingDir::begin(&target).unwrap();
+        fs::write(staging.path().join("genesis.json"), "new").unwrap();
+        fs::create_dir(staging.path().join("keystores")).unwrap();
+        fs::write(staging.path().join("keystores/a.json"), "key").unwrap();
+        staging.commit().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(target.join("genesis.json")).unwrap(),
+            "new"
+        );
+        assert_eq!(
+            fs::read_to_string(target.join("config.yaml")).unwrap(),
+            "PRESET_BASE: minimal"
+        );
+        assert_eq!(
+            fs::read_to_string(target.join("keystores/a.json")).unwrap(),
+            "key"
+        );
+        let leftovers: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(leftovers.len(), 1);
+    }
+
+    #[test]
+    fn test_failed_generation_leaves_no_trace() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("metadata");
+
+        let staging = StagingDir::begin(&target).unwrap();
+        fs::write(staging.path().join("genesis.json"), "partial").unwrap();
+        assert!(StagingDir::begin(&target).is_err());
+        drop(staging);
+
+        assert!(!target.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+        assert!(StagingDir::begin(&target).is_ok());
+    }
+}
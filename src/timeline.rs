@@ -0,0 +1,249 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime};
+use std::fmt::{self, Write};
+
+use crate::el::{
+    consensus_params::FAR_FUTURE_EPOCH,
+    format_registry::Fork,
+    genesis_config::{slots_per_epoch, GenesisConfig},
+};
+
+/// Start of an epoch on a network: its first slot and that slot's timestamp.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EpochTime {
+    pub epoch: u64,
+    pub slot: u64,
+    pub timestamp: u64,
+}
+
+impl EpochTime {
+    /// Fails for epochs, such as `FAR_FUTURE_EPOCH`, that start after the largest timestamp.
+    pub fn at_epoch(genesis_config: &GenesisConfig, epoch: u64) -> Result<Self> {
+        let slot = epoch.checked_mul(slots_per_epoch(&genesis_config.preset_base));
+        let timestamp = genesis_config.checked_activation_time(epoch);
+        let (Some(slot), Some(timestamp)) = (slot, timestamp) else {
+            bail!("epoch {} is in the far future, it never starts", epoch);
+        };
+        Ok(EpochTime {
+            epoch,
+            slot,
+            timestamp,
+        })
+    }
+
+    /// The epoch `timestamp` falls in.
+    pub fn containing(genesis_config: &GenesisConfig, timestamp: u64) -> Result<Self> {
+        let genesis_time = genesis_config.genesis_time();
+        if timestamp < genesis_time {
+            bail!(
+                "{} is before genesis at {} ({})",
+                format_utc(timestamp),
+                genesis_time,
+                format_utc(genesis_time)
+            );
+        }
+        let seconds_per_epoch = genesis_config.seconds_per_epoch();
+        if seconds_per_epoch == 0 {
+            bail!("epochs have no duration with a slot duration of 0 seconds");
+        }
+        EpochTime::at_epoch(
+            genesis_config,
+            (timestamp - genesis_time) / seconds_per_epoch,
+        )
+    }
+}
+
+impl fmt::Display for EpochTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "epoch {}, slot {}, timestamp {} ({})",
+            self.epoch,
+            self.slot,
+            self.timestamp,
+            format_utc(self.timestamp)
+        )
+    }
+}
+
+/// Every fork of `genesis_config` with the epoch, slot and time it activates at.
+pub fn timeline(genesis_config: &GenesisConfig) -> String {
+    let mut forks = vec![("genesis".to_string(), Some(0))];
+    for fork in Fork::ALL {
+        forks.push((
+            format!("{} ({})", fork, fork.cl_name()),
+            fork.activation_epoch(genesis_config),
+        ));
+    }
+    let eip7594_epoch = genesis_config.consensus.eip7594_fork_epoch;
+    forks.push((
+        "eip7594".to_string(),
+        Some(eip7594_epoch).filter(|epoch| *epoch != FAR_FUTURE_EPOCH),
+    ));
+
+    let mut timeline = format!(
+        "{:<24} {:>8} {:>10} {:>12}  {}\n",
+        "fork", "epoch", "slot", "timestamp", "date"
+    );
+    for (name, epoch) in forks {
+        match epoch.map(|epoch| (epoch, EpochTime::at_epoch(genesis_config, epoch))) {
+            Some((_, Ok(time))) => {
+                writeln!(
+                    timeline,
+                    "{:<24} {:>8} {:>10} {:>12}  {}",
+                    name,
+                    time.epoch,
+                    time.slot,
+                    time.timestamp,
+                    format_utc(time.timestamp)
+                )
+            }
+            Some((epoch, Err(_))) => {
+                writeln!(timeline, "{:<24} {:>8}  far future", name, epoch)
+            }
+            None => writeln!(timeline, "{:<24} {:>8}", name, "-"),
+        }
+        .unwrap();
+    }
+    timeline
+}
+
+/// Parses a Unix timestamp or a UTC date such as `2024-05-14T14:00:00Z` or `2024-05-14 14:00`.
+pub fn parse_utc_time(value: &str) -> Result<u64> {
+    let value = value.trim();
+    if let Ok(timestamp) = value.parse() {
+        return Ok(timestamp);
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return timestamp_of(date.timestamp(), value);
+    }
+    for format in [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return timestamp_of(date.and_utc().timestamp(), value);
+        }
+    }
+    bail!(
+        "invalid time {}, expected a Unix timestamp or a UTC date like 2024-05-14T14:00:00Z",
+        value
+    )
+}
+
+/// Parses a duration such as `2h`, `90m` or `1d12h` into seconds.
+pub fn parse_duration(value: &str) -> Result<u64> {
+    let mut seconds = 0u64;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        bail!("empty duration");
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount: u64 = rest[..digits]
+            .parse()
+            .with_context(|| format!("invalid duration {}, expected e.g. 2h or 1d12h", value))?;
+        let unit = match rest[digits..].chars().next() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 60 * 60,
+            Some('d') => 24 * 60 * 60,
+            _ => bail!(
+                "invalid duration {}, every amount needs a unit of s, m, h or d",
+                value
+            ),
+        };
+        seconds = amount
+            .checked_mul(unit)
+            .and_then(|amount| seconds.checked_add(amount))
+            .with_context(|| format!("duration {} is too long", value))?;
+        rest = &rest[digits + 1..];
+    }
+    Ok(seconds)
+}
+
+fn timestamp_of(timestamp: i64, value: &str) -> Result<u64> {
+    u64::try_from(timestamp).map_err(|_| anyhow::anyhow!("{} is before 1970", value))
+}
+
+/// Formats a timestamp as an RFC 3339 UTC date, `None` if chrono cannot represent it.
+pub fn format_rfc3339(timestamp: u64) -> Option<String> {
+    let date = DateTime::from_timestamp(i64::try_from(timestamp).ok()?, 0)?;
+    Some(date.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
+pub fn format_utc(timestamp: u64) -> String {
+    match DateTime::from_timestamp(timestamp as i64, 0) {
+        Some(date) => date.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => "out of range".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn genesis_config() -> GenesisConfig {
+        GenesisConfig {
+            genesis_timestamp: 1_715_688_000,
+            genesis_delay: 60,
+            preset_base: "mainnet".to_string(),
+            slot_duration_in_seconds: 12,
+            capella_fork_epoch: Some(0),
+            deneb_fork_epoch: Some(10),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_converts_between_epochs_and_times() {
+        let genesis_config = genesis_config();
+
+        let deneb = EpochTime::at_epoch(&genesis_config, 10).unwrap();
+        assert_eq!(deneb.slot, 320);
+        assert_eq!(deneb.timestamp, 1_715_688_060 + 10 * 384);
+        assert_eq!(
+            EpochTime::containing(&genesis_config, deneb.timestamp + 383).unwrap(),
+            deneb
+        );
+        assert!(EpochTime::containing(&genesis_config, 1_715_688_059).is_err());
+        assert!(EpochTime::at_epoch(&genesis_config, FAR_FUTURE_EPOCH).is_err());
+
+        assert_eq!(parse_utc_time("1715688060").unwrap(), 1_715_688_060);
+        assert_eq!(
+            parse_utc_time("2024-05-14T12:01:00Z").unwrap(),
+            1_715_688_060
+        );
+        assert_eq!(
+            parse_utc_time("2024-05-14 14:01:00+02:00").unwrap(),
+            1_715_688_060
+        );
+        assert_eq!(parse_utc_time("2024-05-14 12:01").unwrap(), 1_715_688_060);
+        assert!(parse_utc_time("Tuesday").is_err());
+
+        assert_eq!(parse_duration("2h").unwrap(), 7200);
+        assert_eq!(parse_duration("1d12h30s").unwrap(), 129_630);
+        assert!(parse_duration("90").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_timeline_lists_scheduled_forks() {
+        let timeline = timeline(&genesis_config());
+
+        let lines: Vec<&str> = timeline.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[2].starts_with("shanghai (capella)"));
+        assert!(
+            lines[3].contains("1715691900  2024-05-14 13:05:00 UTC"),
+            "{}",
+            lines[3]
+        );
+        assert!(lines[4].trim_end().ends_with('-'));
+    }
+}
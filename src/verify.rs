@@ -0,0 +1,396 @@
+use anyhow::{bail, Context, Result};
+use ethers::types::Address;
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use crate::{
+    cl::genesis_state::GenesisState,
+    el::{
+        consensus_params::FAR_FUTURE_EPOCH,
+        format_registry::FormatRegistry,
+        genesis_config::checked_activation_time,
+        genesis_hash::{genesis_block_hash, parse_u64, state_root},
+        network_genesis::NetworkGenesis,
+    },
+};
+
+/// Outcome of `verify`: every check that ran, and a description of each one that failed.
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub passed: Vec<String>,
+    pub mismatches: Vec<String>,
+}
+
+impl VerifyReport {
+    /// Checks that every source agrees on the value of `what`.
+    fn compare(&mut self, what: &str, values: Vec<(String, String)>) {
+        let Some((_, first)) = values.first() else {
+            return;
+        };
+        if values.iter().all(|(_, value)| value == first) {
+            self.passed.push(format!("{} ({})", what, first));
+        } else {
+            let values: Vec<String> = values
+                .iter()
+                .map(|(source, value)| format!("{} has {}", source, value))
+                .collect();
+            self.mismatches
+                .push(format!("{}: {}", what, values.join(", ")));
+        }
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for passed in &self.passed {
+            writeln!(f, "ok: {}", passed)?;
+        }
+        for mismatch in &self.mismatches {
+            writeln!(f, "MISMATCH: {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+struct ElFile {
+    name: String,
+    genesis: NetworkGenesis,
+    timestamp: u64,
+}
+
+/// Cross-checks the EL genesis files, `config.yaml`, `genesis.ssz` and deposit contract files
+/// of a metadata folder. Files that are absent are not checked.
+pub fn verify(folder: &Path) -> Result<VerifyReport> {
+    let mut el_files = Vec::new();
+    for format in FormatRegistry::default().formats() {
+        let path = folder.join(format.file_name);
+        if path.exists() {
+            let genesis = format.read_network(&path)?;
+            let timestamp = parse_u64(&genesis.header.timestamp)
+                .with_context(|| format!("Invalid timestamp in {}", format.file_name))?;
+            el_files.push(ElFile {
+                name: format.file_name.to_string(),
+                genesis,
+                timestamp,
+            });
+        }
+    }
+    let Some(el) = el_files.first() else {
+        bail!("{} holds no EL genesis file", folder.display());
+    };
+
+    let mut report = VerifyReport::default();
+    report.compare(
+        "chain id",
+        el_values(&el_files, |el_file| {
+            Ok(el_file.genesis.config.chain_id.to_string())
+        })?,
+    );
+    report.compare(
+        "genesis timestamp",
+        el_values(&el_files, |el_file| Ok(el_file.timestamp.to_string()))?,
+    );
+    report.compare(
+        "shanghai time",
+        el_values(&el_files, |el_file| {
+            Ok(el_file.genesis.config.shanghai_time.to_string())
+        })?,
+    );
+    report.compare(
+        "cancun time",
+        el_values(&el_files, |el_file| {
+            Ok(el_file.genesis.config.cancun_time.to_string())
+        })?,
+    );
+    report.compare(
+        "prague time",
+        el_values(&el_files, |el_file| {
+            Ok(optional(el_file.genesis.config.prague_time))
+        })?,
+    );
+    report.compare(
+        "alloc state root",
+        el_values(&el_files, |el_file| {
+            Ok(format!("{:?}", state_root(&el_file.genesis.alloc)))
+        })?,
+    );
+    report.compare(
+        "deposit contract address",
+        el_values(&el_files, |el_file| {
+            normalize_address(&el_file.genesis.config.deposit_contract_address)
+        })?,
+    );
+    let genesis_hash = genesis_block_hash(&el.genesis)?;
+    report.compare(
+        "EL genesis hash",
+        el_values(&el_files, |el_file| {
+            Ok(format!("{:?}", genesis_block_hash(&el_file.genesis)?))
+        })?,
+    );
+
+    let mut preset_base = "mainnet".to_string();
+    let mut cl_genesis_time = None;
+    let config_yaml = folder.join("config.yaml");
+    if config_yaml.exists() {
+        let cl_config = read_cl_config(&config_yaml)?;
+        let cl_value = |key: &str| -> Result<Option<u64>> {
+            cl_config
+                .get(key)
+                .map(|value| {
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid {} in config.yaml", key))
+                })
+                .transpose()
+        };
+        if let Some(preset) = cl_config.get("PRESET_BASE") {
+            preset_base = preset.clone();
+        }
+        let genesis_delay = cl_value("GENESIS_DELAY")?.unwrap_or(0);
+        let seconds_per_slot = cl_value("SECONDS_PER_SLOT")?.unwrap_or(12);
+        cl_genesis_time = Some(el.timestamp + genesis_delay);
+
+        let forks = [
+            (
+                "CAPELLA_FORK_EPOCH",
+                "shanghai time",
+                Some(el.genesis.config.shanghai_time),
+            ),
+            (
+                "DENEB_FORK_EPOCH",
+                "cancun time",
+                Some(el.genesis.config.cancun_time),
+            ),
+            (
+                "ELECTRA_FORK_EPOCH",
+                "prague time",
+                el.genesis.config.prague_time,
+            ),
+        ];
+        for (epoch_key, what, el_time) in forks {
+            let Some(epoch) = cl_value(epoch_key)? else {
+                continue;
+            };
+            let cl_time = checked_activation_time(
+                el.timestamp,
+                genesis_delay,
+                &preset_base,
+                seconds_per_slot,
+                epoch,
+            )
+            .filter(|_| epoch != FAR_FUTURE_EPOCH);
+            report.compare(
+                &format!("{} of {}", what, epoch_key),
+                vec![
+                    ("config.yaml".to_string(), optional(cl_time)),
+                    (el.name.clone(), optional(el_time)),
+                ],
+            );
+        }
+        if let Some(chain_id) = cl_config.get("DEPOSIT_CHAIN_ID") {
+            report.compare(
+                "deposit chain id",
+                vec![
+                    ("config.yaml".to_string(), chain_id.clone()),
+                    (el.name.clone(), el.genesis.config.chain_id.to_string()),
+                ],
+            );
+        }
+        if let Some(address) = cl_config.get("DEPOSIT_CONTRACT_ADDRESS") {
+            report.compare(
+                "deposit contract address in config.yaml",
+                vec![
+                    ("config.yaml".to_string(), normalize_address(address)?),
+                    (
+                        el.name.clone(),
+                        normalize_address(&el.genesis.config.deposit_contract_address)?,
+                    ),
+                ],
+            );
+        }
+    }
+
+    let genesis_ssz = folder.join("genesis.ssz");
+    if genesis_ssz.exists() {
+        let state = GenesisState::read(&genesis_ssz, &preset_base)?;
+        match &state.execution_payload_header {
+            Some(header) => {
+                report.compare(
+                    "genesis.ssz execution block hash",
+                    vec![
+                        (
+                            "genesis.ssz".to_string(),
+                            format!("{:?}", header.block_hash),
+                        ),
+                        (el.name.clone(), format!("{:?}", genesis_hash)),
+                    ],
+                );
+                report.compare(
+                    "genesis.ssz execution block timestamp",
+                    vec![
+                        ("genesis.ssz".to_string(), header.timestamp.to_string()),
+                        (el.name.clone(), el.timestamp.to_string()),
+                    ],
+                );
+            }
+            None => report
+                .mismatches
+                .push("genesis.ssz has no execution payload header".to_string()),
+        }
+        if let Some(cl_genesis_time) = cl_genesis_time {
+            report.compare(
+                "CL genesis time",
+                vec![
+                    ("genesis.ssz".to_string(), state.genesis_time.to_string()),
+                    ("config.yaml".to_string(), cl_genesis_time.to_string()),
+                ],
+            );
+        }
+    }
+
+    if let Some(address) = read_text(folder, "deposit_contract.txt")? {
+        report.compare(
+            "deposit contract address in deposit_contract.txt",
+            vec![
+                (
+                    "deposit_contract.txt".to_string(),
+                    normalize_address(&address)?,
+                ),
+                (
+                    el.name.clone(),
+                    normalize_address(&el.genesis.config.deposit_contract_address)?,
+                ),
+            ],
+        );
+    }
+    let mut deposit_blocks = Vec::new();
+    for file in ["deposit_contract_block.txt", "deploy_block.txt"] {
+        if let Some(block) = read_text(folder, file)? {
+            deposit_blocks.push((file.to_string(), block));
+        }
+    }
+    report.compare("deposit contract block", deposit_blocks.clone());
+    if let (Some((_, block)), Some(block_hash)) = (
+        deposit_blocks.first(),
+        read_text(folder, "deposit_contract_block_hash.txt")?,
+    ) {
+        if block == "0" {
+            report.compare(
+                "deposit contract block hash",
+                vec![
+                    (
+                        "deposit_contract_block_hash.txt".to_string(),
+                        block_hash.to_lowercase(),
+                    ),
+                    (el.name.clone(), format!("{:?}", genesis_hash)),
+                ],
+            );
+        }
+    }
+    Ok(report)
+}
+
+/// The value of every EL file, labelled with the file name.
+fn el_values(
+    el_files: &[ElFile],
+    value: impl Fn(&ElFile) -> Result<String>,
+) -> Result<Vec<(String, String)>> {
+    el_files
+        .iter()
+        .map(|el_file| Ok((el_file.name.clone(), value(el_file)?)))
+        .collect()
+}
+
+/// Keys of `config.yaml` with their values as written.
+pub(crate) fn read_cl_config(path: &Path) -> Result<BTreeMap<String, String>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let values: BTreeMap<String, serde_yaml::Value> = serde_yaml::from_str(&content)
+        .with_context(|| format!("Cannot parse {}", path.display()))?;
+    Ok(values
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                serde_yaml::Value::String(value) => value,
+                serde_yaml::Value::Number(value) => value.to_string(),
+                serde_yaml::Value::Bool(value) => value.to_string(),
+                _ => return None,
+            };
+            Some((key, value))
+        })
+        .collect())
+}
+
+fn read_text(folder: &Path, file: &str) -> Result<Option<String>> {
+    let path = folder.join(file);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+    Ok(Some(content.trim().to_string()))
+}
+
+fn normalize_address(address: &str) -> Result<String> {
+    let address: Address = address
+        .parse()
+        .with_context(|| format!("Invalid address {}", address))?;
+    Ok(format!("{:?}", address))
+}
+
+fn optional(value: Option<u64>) -> String {
+    value.map_or_else(|| "unscheduled".to_string(), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::el::fixtures::geth_genesis;
+    use serde_json::{json, Value};
+
+    fn genesis(chain_id: u64) -> Value {
+        geth_genesis(
+            1700000000,
+            json!({
+                "chainId": chain_id, "shanghaiTime": 1700000096, "cancunTime": 1700000480
+            }),
+            json!({"0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266": {"balance": "1ETH"}}),
+        )
+    }
+
+    #[test]
+    fn test_reports_mismatches_across_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("genesis.json"), genesis(1337).to_string()).unwrap();
+        fs::write(dir.path().join("besu.json"), genesis(1337).to_string()).unwrap();
+        fs::write(
+            dir.path().join("config.yaml"),
+            "PRESET_BASE: minimal\nGENESIS_DELAY: 96\nSECONDS_PER_SLOT: 6\n\
+             CAPELLA_FORK_EPOCH: 0\nDENEB_FORK_EPOCH: 8\nELECTRA_FORK_EPOCH: 18446744073709551615\n\
+             DEPOSIT_CHAIN_ID: 1337\n\
+             DEPOSIT_CONTRACT_ADDRESS: 0x4242424242424242424242424242424242424242\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("deposit_contract.txt"),
+            "0x4242424242424242424242424242424242424242\n",
+        )
+        .unwrap();
+
+        let report = verify(dir.path()).unwrap();
+        assert_eq!(report.mismatches, Vec::<String>::new(), "{}", report);
+        assert!(report.passed.len() > 10);
+
+        fs::write(dir.path().join("besu.json"), genesis(1338).to_string()).unwrap();
+        fs::write(dir.path().join("deploy_block.txt"), "0").unwrap();
+        fs::write(dir.path().join("deposit_contract_block.txt"), "5").unwrap();
+
+        let report = verify(dir.path()).unwrap();
+        assert_eq!(
+            report.mismatches,
+            [
+                "chain id: genesis.json has 1337, besu.json has 1338",
+                "deposit contract block: deposit_contract_block.txt has 5, deploy_block.txt has 0",
+            ]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use ethereum_genesis_generator_rs::{
+    cl::config::cl_config,
+    defaults_env::read_defaults,
+    el::{
+        config_keys::ConfigMode,
+        consensus_params::{ConsensusParams, ForkVersion},
+        genesis_config::{GenesisConfig, GenesisConfigBuilder},
+    },
+};
+use ethers::types::{Address, U256};
+use std::collections::HashMap;
+
+fn read(fixture: &str) -> HashMap<String, String> {
+    let path = format!(
+        "{}/tests/fixtures/upstream/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        fixture
+    );
+    read_defaults(&path).unwrap()
+}
+
+/// Loads `fixture`, checking that strict mode only rejects `unsupported`, the CL genesis
+/// state keys it sets. Any other key of the upstream file that is not understood fails.
+fn load(fixture: &str, unsupported: &[&str]) -> GenesisConfig {
+    let error = match GenesisConfigBuilder::new()
+        .config_mode(ConfigMode::Strict)
+        .from_hashmap(read(fixture))
+    {
+        Ok(_) => panic!("{} sets no unsupported keys", fixture),
+        Err(error) => error,
+    };
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "unsupported config keys: {} (the CL genesis state is not generated)",
+            unsupported.join(", ")
+        )
+    );
+
+    let mut context = read(fixture);
+    for key in unsupported {
+        context.remove(*key);
+    }
+    GenesisConfigBuilder::new()
+        .config_mode(ConfigMode::Strict)
+        .from_hashmap(context)
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_upstream_defaults() {
+    let config = load(
+        "defaults.env",
+        &[
+            "WITHDRAWAL_TYPE",
+            "WITHDRAWAL_ADDRESS",
+            "CL_EXEC_BLOCK",
+            "DEPOSIT_CONTRACT_BLOCK",
+            "BEACON_STATIC_ENR",
+        ],
+    );
+
+    assert_eq!(config.preset_base, "mainnet");
+    assert_eq!(config.chain_id, 1337);
+    assert_eq!(config.genesis_delay, 60);
+    assert_eq!(config.genesis_gaslimit, Some(25_000_000));
+    assert_eq!(config.capella_fork_epoch, Some(0));
+    assert_eq!(config.deneb_fork_epoch, Some(0));
+    // Upstream schedules unused forks at the far future epoch, which is not a fork time.
+    assert_eq!(config.electra_fork_epoch, None);
+    assert_eq!(config.eof_activation_epoch, None);
+    assert!(config.el_premine_addrs.is_empty());
+
+    let defaults = ConsensusParams::default();
+    assert_eq!(
+        config.consensus.number_of_validators,
+        defaults.number_of_validators
+    );
+    assert_eq!(
+        config.consensus.genesis_fork_version,
+        defaults.genesis_fork_version
+    );
+    assert_eq!(config.consensus.eip7594_fork_epoch, u64::MAX);
+}
+
+#[test]
+fn test_upstream_values_env() {
+    let config = load(
+        "values.env",
+        &[
+            "WITHDRAWAL_TYPE",
+            "WITHDRAWAL_ADDRESS",
+            "CL_EXEC_BLOCK",
+            "DEPOSIT_CONTRACT_BLOCK",
+            "BEACON_STATIC_ENR",
+        ],
+    );
+
+    assert_eq!(config.chain_id, 3151908);
+    assert_eq!(config.deneb_fork_epoch, Some(0));
+    assert_eq!(config.electra_fork_epoch, None);
+    assert!(config.additional_preloaded_contracts.is_empty());
+    assert_eq!(config.consensus.data_column_sidecar_subnet_count, 32);
+
+    let cl_config = cl_config(&config);
+    let value = |key: &str| {
+        cl_config
+            .iter()
+            .find(|(entry, _)| *entry == key)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(value("MIN_GENESIS_ACTIVE_VALIDATOR_COUNT"), Some("64"));
+    assert_eq!(value("DEPOSIT_CHAIN_ID"), Some("3151908"));
+    assert_eq!(value("ELECTRA_FORK_EPOCH"), Some("18446744073709551615"));
+    assert_eq!(value("DATA_COLUMN_SIDECAR_SUBNET_COUNT"), Some("32"));
+}
+
+#[test]
+fn test_upstream_minimal_devnet() {
+    let config = load(
+        "minimal-devnet.env",
+        &[
+            "WITHDRAWAL_TYPE",
+            "WITHDRAWAL_ADDRESS",
+            "CL_EXEC_BLOCK",
+            "DEPOSIT_CONTRACT_BLOCK",
+        ],
+    );
+    let withdrawal_address: Address = "0x8943545177806ED17B9F23F0a21ee5948eCaa776"
+        .parse()
+        .unwrap();
+
+    assert_eq!(config.preset_base, "minimal");
+    assert_eq!(config.chain_id, 3151908);
+    assert_eq!(config.slot_duration_in_seconds, 6);
+    assert_eq!(config.deneb_fork_epoch, Some(1));
+    assert_eq!(config.electra_fork_epoch, Some(2));
+    assert_eq!(
+        config.el_premine_addrs[&withdrawal_address].balance,
+        U256::from(1_000_000_000u64) * U256::exp10(18)
+    );
+
+    let consensus = config.consensus;
+    assert_eq!(consensus.number_of_validators, 128);
+    assert_eq!(
+        consensus.deneb_fork_version,
+        ForkVersion([0x50, 0, 0, 0x38])
+    );
+    assert_eq!(consensus.max_per_epoch_activation_churn_limit, 4);
+    assert_eq!(consensus.churn_limit_quotient, 32);
+    assert_eq!(consensus.eth1_follow_distance, 16);
+    assert_eq!(consensus.shard_committee_period, 1);
+}